@@ -1,12 +1,18 @@
 use anyhow::anyhow;
-use nom::Finish;
+use clap::Parser;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs::File;
 use std::io::{stdin, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::dimacs::Dimacs;
-use core::solvers::{build_command, parse_solver};
-use core::{CnfClause, CnfSat, EvaluationResult};
-
-mod dimacs;
+use core::cli::CommonArgs;
+use core::report::{RunReport, SolveRecord};
+use core::solvers::{parse_solver_strict, Solver};
+use core::{CnfSat, EvaluationResult, Literal, SatModel, VarId};
+use flate2::read::MultiGzDecoder;
+use serde_json::json;
 
 #[derive(Eq, PartialEq, Copy, Clone)]
 enum VariableValue {
@@ -17,33 +23,340 @@ enum VariableValue {
     Backbone(bool),
 }
 
+/// Finds the backbones of a CNF formula read from stdin in DIMACS format.
+#[derive(Parser)]
+#[command(name = "backbones")]
+struct Cli {
+    #[command(flatten)]
+    common: CommonArgs,
+
+    /// Stop the search as soon as this many backbones are confirmed.
+    #[arg(long)]
+    min_backbones: Option<usize>,
+
+    /// Write the clause and variable counts at every solver call to this file as CSV.
+    #[arg(long)]
+    db_stats: Option<PathBuf>,
+
+    /// Print every backbone as a JSON object as soon as it is confirmed, then a summary object.
+    #[arg(long, conflicts_with = "csv")]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Option<Subcommand>,
+}
+
+#[derive(clap::Subcommand)]
+enum Subcommand {
+    /// Find the backbones of every `.cnf` or gzipped `.cnf.gz` file in a directory and print a summary row
+    /// for each.
+    Batch { dir: PathBuf },
+}
+
 fn main() -> Result<(), anyhow::Error> {
-    let args = std::env::args().collect();
-    let solver = parse_solver(args);
-    eprintln!("Using solver {solver:?}");
+    let cli = Cli::parse();
+    let args = cli.common;
+    let solver = parse_solver_strict(&args).map_err(anyhow::Error::msg)?;
+
+    if let Some(Subcommand::Batch { dir }) = &cli.command {
+        return run_batch(dir, &args, &solver, cli.min_backbones);
+    }
 
     let mut input = String::new();
     stdin().read_to_string(&mut input)?;
+    let (sat, vars) = read_sat(&input)?;
+
+    let solver = args.resolve_solver(&solver)?;
+    if !args.quiet {
+        eprintln!("Using solver {solver:?}");
+    }
+
+    let mut report = RunReport {
+        solver: format!("{solver:?}"),
+        ..Default::default()
+    };
 
-    let dimacs = match dimacs::parse(&input).finish() {
-        Ok((_, dimacs)) => dimacs,
-        Err(err) => {
-            return Err(anyhow!(
-                "Failed to parse dimacs: {}",
-                nom::error::convert_error(input.as_str(), err)
-            ));
+    if !args.quiet {
+        eprintln!("Input CNF: {}", sat.stats());
+    }
+
+    if args.describe {
+        eprint!("{}", sat.to_readable());
+    }
+
+    if let Some(runs) = args.runs {
+        let timing = sat.benchmark(
+            |seed| args.build_command_with_seed(&solver, seed).unwrap_or_else(|err| panic!("{err}")),
+            runs,
+        );
+        println!("Solved {timing}");
+        return Ok(());
+    }
+
+    // (iteration, clause count, variable count) at each solver call, collected only when requested.
+    let mut db_snapshots: Vec<(usize, usize, usize)> = Vec::new();
+    let mut solver_calls = 0;
+    let mut total_time = Duration::ZERO;
+
+    let backbones = search_backbones(
+        &sat,
+        &vars,
+        cli.min_backbones,
+        args.quiet,
+        |sat, assumptions, candidate| {
+            // The assumptions reach the solver as unit clauses.
+            let clause_count = sat.clause_count() + assumptions.len();
+            if cli.db_stats.is_some() {
+                db_snapshots.push((db_snapshots.len(), clause_count, sat.variable_count()));
+            }
+
+            let command = args.build_command(&solver)?;
+            let result = sat.try_evaluate_with_assumptions(command, assumptions)?;
+            solver_calls += 1;
+            total_time += result.solve_time();
+            if cli.json {
+                for backbone in json_backbones(sat, &vars, candidate, &result) {
+                    println!("{backbone}");
+                }
+            }
+            if args.report.is_some() {
+                let label = match candidate {
+                    None => "initial".to_string(),
+                    Some((index, value)) => format!("{}={}", vars[index].dimacs(), value),
+                };
+                report.solves.push(SolveRecord::new(
+                    &label,
+                    sat.variable_count(),
+                    clause_count,
+                    &result,
+                ));
+            }
+            Ok(result)
+        },
+    )?
+    .ok_or_else(|| anyhow!("Unsatisfiable CNF input provided."))?;
+
+    if cli.json {
+        println!("{}", json_summary(&sat, &backbones, solver_calls, total_time));
+    } else if args.csv {
+        println!("variable,value");
+        for backbone in &backbones {
+            println!("{},{}", backbone.abs(), *backbone > 0);
+        }
+    } else {
+        println!("Found {} backbones:", backbones.len());
+        println!("{}", backbones.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" "));
+        if let Some(target) = cli.min_backbones {
+            if backbones.len() >= target {
+                println!("Target of {target} backbones reached, search stopped early.");
+            } else {
+                println!("Search exhausted, target of {target} backbones not reached.");
+            }
         }
+    }
+
+    if let Some(path) = &cli.db_stats {
+        let mut csv = String::from("iteration,clauses,variables\n");
+        for (iteration, clauses, variables) in db_snapshots {
+            let _ = writeln!(csv, "{iteration},{clauses},{variables}");
+        }
+        std::fs::write(path, csv)?;
+    }
+
+    if let Some(path) = &args.report {
+        report.backbones = Some(backbones);
+        report.write(path, args.format)?;
+    }
+
+    Ok(())
+}
+
+/// The backbones confirmed by a solver call for `--json`: the unit clauses once the formula is known
+/// to be satisfiable, or a candidate whose negation is unsatisfiable.
+fn json_backbones(
+    sat: &CnfSat,
+    vars: &[VarId],
+    candidate: Option<(usize, bool)>,
+    result: &EvaluationResult,
+) -> Vec<serde_json::Value> {
+    match (candidate, result) {
+        (None, EvaluationResult::Sat { .. }) => sat
+            .unit_literals()
+            .into_iter()
+            .map(|(id, value)| json!({ "variable": id.dimacs(), "value": value, "time_seconds": 0.0 }))
+            .collect(),
+        (Some((index, value)), EvaluationResult::Unsat { .. }) => vec![json!({
+            "variable": vars[index].dimacs(),
+            "value": value,
+            "time_seconds": result.solve_time().as_secs_f64(),
+        })],
+        _ => Vec::new(),
+    }
+}
+
+fn json_summary(sat: &CnfSat, backbones: &[i64], solver_calls: usize, total_time: Duration) -> serde_json::Value {
+    json!({
+        "backbones": backbones.len(),
+        "variables": sat.variable_count(),
+        "solver_calls": solver_calls,
+        "total_time_seconds": total_time.as_secs_f64(),
+    })
+}
+
+/// Find the backbones of every `.cnf` or `.cnf.gz` file in the directory and print a row for each.
+/// Files that fail are reported in their row and do not stop the batch.
+fn run_batch(
+    dir: &Path,
+    args: &CommonArgs,
+    solver: &Solver,
+    min_backbones: Option<usize>,
+) -> Result<(), anyhow::Error> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|path| {
+        let name = path.to_string_lossy();
+        name.ends_with(".cnf") || name.ends_with(".cnf.gz")
+    });
+    paths.sort();
+
+    if args.csv {
+        println!("file,variables,clauses,result,time_seconds,backbones,error");
+    }
+
+    for path in paths {
+        let mut row = BatchRow::default();
+        if let Err(err) = solve_batch_file(&path, args, solver, min_backbones, &mut row) {
+            row.error = Some(err.to_string());
+        }
+
+        let file = path.file_name().unwrap_or_default().to_string_lossy();
+        let backbones = row.backbones.map(|count| count.to_string()).unwrap_or_default();
+        if args.csv {
+            println!(
+                "{},{},{},{},{},{},{}",
+                csv_field(&file),
+                row.variables,
+                row.clauses,
+                row.result,
+                row.time.as_secs_f64(),
+                backbones,
+                csv_field(&row.error.unwrap_or_default())
+            );
+        } else {
+            match row.error {
+                Some(error) => println!("{file}: error: {error}"),
+                None => println!(
+                    "{file}: {} vars, {} clauses, {} in {:?}, {} backbones",
+                    row.variables,
+                    row.clauses,
+                    row.result,
+                    row.time,
+                    if backbones.is_empty() { "-" } else { &backbones }
+                ),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The value as a CSV field, quoted if it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Default)]
+struct BatchRow {
+    variables: usize,
+    clauses: usize,
+    result: &'static str,
+    /// Total time of all the solver calls.
+    time: Duration,
+    backbones: Option<usize>,
+    error: Option<String>,
+}
+
+fn solve_batch_file(
+    path: &Path,
+    args: &CommonArgs,
+    solver: &Solver,
+    min_backbones: Option<usize>,
+    row: &mut BatchRow,
+) -> Result<(), anyhow::Error> {
+    let input = read_input_file(path)?;
+    let (sat, vars) = read_sat(&input)?;
+    row.variables = sat.variable_count();
+    row.clauses = sat.clause_count();
+
+    let solver = &args.resolve_solver(solver)?;
+    let backbones = search_backbones(&sat, &vars, min_backbones, true, |sat, assumptions, _| {
+        let result = sat.try_evaluate_with_assumptions(args.build_command(solver)?, assumptions)?;
+        row.time += result.solve_time();
+        Ok(result)
+    })?;
+
+    row.result = match backbones {
+        Some(_) => "SAT",
+        None => "UNSAT",
     };
+    row.backbones = backbones.map(|backbones| backbones.len());
+    Ok(())
+}
 
-    let (mut sat, vars) = dimacs_to_sat(dimacs);
+/// The contents of the file, decompressed if it has the `.gz` extension.
+fn read_input_file(path: &Path) -> Result<String, anyhow::Error> {
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        let mut input = String::new();
+        MultiGzDecoder::new(File::open(path)?).read_to_string(&mut input)?;
+        Ok(input)
+    } else {
+        Ok(std::fs::read_to_string(path)?)
+    }
+}
 
-    eprintln!(
-        "Input CNF: {} vars, {} clauses",
-        sat.variable_count(),
-        sat.clause_count()
-    );
+fn read_sat(input: &str) -> Result<(CnfSat, Vec<VarId>), anyhow::Error> {
+    let sat = CnfSat::from_dimacs(input)?;
+    let vars = sat.all_variables().collect();
+    Ok((sat, vars))
+}
+
+/// Backbones of the formula as DIMACS literals, or `None` if it is unsatisfiable.
+///
+/// The formula is solved by `solve` under the given assumptions, which also receives
+/// the candidate being checked, `None` for the initial solve. Stops early once `min_backbones` are found.
+fn search_backbones(
+    sat: &CnfSat,
+    vars: &[VarId],
+    min_backbones: Option<usize>,
+    quiet: bool,
+    mut solve: impl FnMut(
+        &CnfSat,
+        &[Literal],
+        Option<(usize, bool)>,
+    ) -> Result<EvaluationResult, anyhow::Error>,
+) -> Result<Option<Vec<i64>>, anyhow::Error> {
+    if sat.is_trivially_unsat() {
+        return Ok(None);
+    }
 
-    let mut assignments: Vec<_> = vars.iter().map(|_| VariableValue::None).collect();
+    // Variables in unit clauses are backbones without asking the solver.
+    let units: HashMap<_, _> = sat.unit_literals().into_iter().collect();
+    let mut backbone_count = 0;
+    let mut assignments: Vec<_> = vars
+        .iter()
+        .map(|var| match units.get(var) {
+            Some(&value) => {
+                backbone_count += 1;
+                VariableValue::Backbone(value)
+            }
+            None => VariableValue::None,
+        })
+        .collect();
 
     #[derive(Eq, PartialEq, Copy, Clone, Debug)]
     enum State {
@@ -57,25 +370,32 @@ fn main() -> Result<(), anyhow::Error> {
     let mut state = State::FirstRun;
 
     loop {
-        if let State::Searching {
-            candidate_index,
-            candidate_value,
-        } = state
-        {
-            let mut clause = CnfClause::new();
-            // Try to add negated literal, if adding it is UNSAT -> this is a backbone.
-            clause.set(vars[candidate_index], !candidate_value);
-            sat.add_clause(clause);
-        }
+        let (candidate, assumptions) = match state {
+            State::FirstRun => (None, vec![]),
+            State::Searching {
+                candidate_index,
+                candidate_value,
+            } => {
+                // Try to assume the negated literal, if that is UNSAT -> this is a backbone.
+                let assumption = (vars[candidate_index], !candidate_value);
+                (Some((candidate_index, candidate_value)), vec![assumption])
+            }
+        };
+        let result = solve(sat, &assumptions, candidate)?;
 
-        let result = sat.evaluate(build_command(&solver));
         match result {
-            EvaluationResult::Sat { model, time, .. } => {
-                eprintln!("Finished in {time:?}, SAT");
+            EvaluationResult::Sat { model, solve_time, .. } => {
+                if !quiet {
+                    eprintln!("Finished in {solve_time:?}, SAT");
+                }
                 for (i, &var) in vars.iter().enumerate() {
-                    let result_bool = model
-                        .get_result_by_id(var)
-                        .expect("Var missing in solver output");
+                    // Solvers may leave out variables that do not matter, these take either value.
+                    let Some(result_bool) = model.get_result_by_id(var) else {
+                        if !matches!(assignments[i], VariableValue::Backbone(_)) {
+                            assignments[i] = VariableValue::Either;
+                        }
+                        continue;
+                    };
 
                     let result = match result_bool {
                         true => VariableValue::True,
@@ -96,7 +416,7 @@ fn main() -> Result<(), anyhow::Error> {
                             backbone
                         }
                         // If this result matches all seen values, keep it, otherwise swap to Either.
-                        value @ _ => {
+                        value => {
                             if result == value {
                                 value
                             } else {
@@ -105,27 +425,46 @@ fn main() -> Result<(), anyhow::Error> {
                         }
                     }
                 }
+
+                // Model rotation: a variable that can be flipped in this model without falsifying
+                // any clause takes both values, so it needs no solver call of its own.
+                let rotatable: HashSet<_> = sat.rotatable_variables(&model).into_iter().collect();
+                for (i, var) in vars.iter().enumerate() {
+                    if rotatable.contains(var) {
+                        if let VariableValue::True | VariableValue::False = assignments[i] {
+                            assignments[i] = VariableValue::Either;
+                        }
+                    }
+                }
             }
 
-            EvaluationResult::Unsat { time, .. } => {
-                eprintln!("Finished in {time:?}, UNSAT");
+            EvaluationResult::Unsat { solve_time, .. } => {
+                if !quiet {
+                    eprintln!("Finished in {solve_time:?}, UNSAT");
+                }
                 match state {
-                    State::FirstRun => {
-                        return Err(anyhow!("Unsatisfiable CNF input provided."));
-                    }
+                    State::FirstRun => return Ok(None),
                     State::Searching {
                         candidate_index,
                         candidate_value,
                     } => {
                         assignments[candidate_index] = VariableValue::Backbone(candidate_value);
+                        backbone_count += 1;
                     }
                 }
             }
+
+            EvaluationResult::Unknown { solve_time, .. } => {
+                return Err(anyhow!("Solver gave up after {solve_time:?}."));
+            }
+
+            EvaluationResult::Timeout { solve_time, .. } => {
+                return Err(anyhow!("Solver timed out after {solve_time:?}."));
+            }
         }
 
-        if let State::Searching { .. } = state {
-            // Remove the clause we added for checking the backbone.
-            sat.pop_clause();
+        if min_backbones.is_some_and(|target| backbone_count >= target) {
+            break;
         }
 
         state = match state {
@@ -148,21 +487,30 @@ fn main() -> Result<(), anyhow::Error> {
         }
     }
 
-    let backbones: Vec<_> = assignments
+    let backbone_model = backbone_model(sat, vars, &assignments);
+    let backbones = vars
         .iter()
         .enumerate()
-        .filter(|(_, x)| matches!(x, VariableValue::Backbone(_)))
-        .map(|(i, x)| match x {
-            VariableValue::Backbone(true) => format!("{}", i + 1),
-            VariableValue::Backbone(false) => format!("-{}", i + 1),
-            _ => unreachable!(),
+        .filter_map(|(i, &var)| match backbone_model.get_result_by_id(var)? {
+            true => Some(i as i64 + 1),
+            false => Some(-(i as i64 + 1)),
         })
         .collect();
 
-    println!("Found {} backbones:", backbones.len());
-    println!("{}", backbones.join(" "));
+    Ok(Some(backbones))
+}
 
-    Ok(())
+/// A model assigning only the variables confirmed as backbones, to their forced values.
+fn backbone_model(sat: &CnfSat, vars: &[VarId], assignments: &[VariableValue]) -> SatModel {
+    let forced: Vec<_> = vars
+        .iter()
+        .zip(assignments)
+        .filter_map(|(&var, value)| match value {
+            VariableValue::Backbone(value) => Some((var, *value)),
+            _ => None,
+        })
+        .collect();
+    SatModel::from_vec(sat, &forced)
 }
 
 fn find_backbone_candidate(
@@ -186,32 +534,140 @@ fn find_backbone_candidate(
     ))
 }
 
-fn dimacs_to_sat(dimacs: Dimacs) -> (CnfSat, Vec<usize>) {
-    let mut cnf = CnfSat::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("plain.cnf"), "plain.cnf");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("a,b.cnf"), "\"a,b.cnf\"");
+        assert_eq!(csv_field("say \"hi\".cnf"), "\"say \"\"hi\"\".cnf\"");
+        assert_eq!(csv_field("failed:\nline two"), "\"failed:\nline two\"");
+    }
+
+    #[test]
+    fn compressed_files_are_decompressed() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let dimacs = "p cnf 2 1\n1 -2 0\n";
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("plain.cnf");
+        std::fs::write(&plain, dimacs).unwrap();
+        let compressed = dir.path().join("compressed.cnf.gz");
+        let mut encoder = GzEncoder::new(File::create(&compressed).unwrap(), Compression::default());
+        encoder.write_all(dimacs.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(read_input_file(&plain).unwrap(), dimacs);
+        assert_eq!(read_input_file(&compressed).unwrap(), dimacs);
+    }
+
+    /// Solves by trying every assignment in order, counting the calls.
+    fn brute_force(sat: &CnfSat, assumptions: &[Literal], calls: &mut usize) -> EvaluationResult {
+        *calls += 1;
+        let ids: Vec<_> = sat.all_variables().collect();
+        for assignment in 0u32..1 << ids.len() {
+            let literals: Vec<_> = (0..ids.len()).map(|i| (ids[i], assignment & (1 << i) != 0)).collect();
+            let model = SatModel::from_vec(sat, &literals);
+            if assumptions.iter().all(|literal| literals.contains(literal)) && sat.verify_model(&model).is_ok() {
+                return EvaluationResult::Sat {
+                    dimacs: String::new(),
+                    model,
+                    solver: String::new(),
+                    solve_time: Duration::ZERO,
+                    total_time: Duration::ZERO,
+                };
+            }
+        }
+        EvaluationResult::Unsat {
+            dimacs: String::new(),
+            core: assumptions.to_vec(),
+            solver: String::new(),
+            solve_time: Duration::ZERO,
+            total_time: Duration::ZERO,
+        }
+    }
 
-    let vars: Vec<_> = (0..dimacs.variable_count())
-        .map(|var| {
-            let name = format!("{}", var);
-            cnf.create_variable(&name);
-            cnf.get_variable(&name)
+    #[test]
+    fn rotation_saves_solver_calls() {
+        // a is forced by the first two clauses, b, c, d and e are not.
+        let (sat, vars) = read_sat("p cnf 5 3\n1 2 0\n1 -2 0\n3 4 5 0\n").unwrap();
+        let mut calls = 0;
+        let backbones = search_backbones(&sat, &vars, None, true, |sat, assumptions, _| {
+            Ok(brute_force(sat, assumptions, &mut calls))
         })
-        .collect();
+        .unwrap();
+        assert_eq!(backbones, Some(vec![1]));
+        // The first model sets a and c, b, d and e can be flipped in it. Only a and c need a check.
+        assert_eq!(calls, 3);
+    }
 
-    for dimacs_clause in dimacs.clauses() {
-        let mut clause = CnfClause::new();
-        for literal in dimacs_clause.literals() {
-            match literal {
-                dimacs::Literal::Positive(variable) => {
-                    clause.set(vars[(variable - 1) as usize], true);
-                }
-                dimacs::Literal::Negative(variable) => {
-                    clause.set(vars[(variable - 1) as usize], false);
-                }
+    #[test]
+    fn variables_missing_from_the_model_are_not_backbones() {
+        let (sat, vars) = read_sat("p cnf 3 2\n1 0\n1 2 3 0\n").unwrap();
+        let backbones = search_backbones(&sat, &vars, None, true, |sat, assumptions, _| {
+            let mut calls = 0;
+            let EvaluationResult::Sat { model, .. } = brute_force(sat, assumptions, &mut calls) else {
+                panic!("The formula is satisfiable");
             };
-        }
+            // Leave out the last variable, as solvers do with variables that do not matter.
+            let partial: Vec<_> = model.iter_ids().filter(|&(id, _)| id != vars[2]).collect();
+            let partial = SatModel::from_vec(sat, &partial);
+            Ok(EvaluationResult::Sat {
+                dimacs: String::new(),
+                model: partial,
+                solver: String::new(),
+                solve_time: Duration::ZERO,
+                total_time: Duration::ZERO,
+            })
+        })
+        .unwrap();
+        assert_eq!(backbones, Some(vec![1]));
+    }
+
+    #[test]
+    fn json_lines_parse() {
+        let (sat, vars) = read_sat("p cnf 3 3\n1 0\n2 3 0\n2 -3 0\n").unwrap();
+        let mut lines = Vec::new();
+        let mut calls = 0;
+        let backbones = search_backbones(&sat, &vars, None, true, |sat, assumptions, candidate| {
+            let result = brute_force(sat, assumptions, &mut calls);
+            lines.extend(json_backbones(sat, &vars, candidate, &result).iter().map(|line| line.to_string()));
+            Ok(result)
+        })
+        .unwrap()
+        .unwrap();
+        lines.push(json_summary(&sat, &backbones, calls, Duration::ZERO).to_string());
 
-        cnf.add_clause(clause);
+        let parsed: Vec<serde_json::Value> = lines.iter().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(parsed.len(), 3);
+        for (backbone, variable) in parsed[..2].iter().zip([1, 2]) {
+            assert_eq!(backbone["variable"], variable);
+            assert_eq!(backbone["value"], true);
+            assert!(backbone["time_seconds"].is_f64());
+        }
+        assert_eq!(parsed[2]["backbones"], 2);
+        assert_eq!(parsed[2]["variables"], 3);
+        assert_eq!(parsed[2]["solver_calls"], calls);
+        assert!(parsed[2]["total_time_seconds"].is_f64());
     }
 
-    (cnf, vars)
+    #[test]
+    fn unit_clauses_need_no_solver_calls() {
+        let (sat, vars) = read_sat("p cnf 3 3\n1 0\n-2 0\n1 3 0\n").unwrap();
+        let mut candidates = Vec::new();
+        let mut calls = 0;
+        let backbones = search_backbones(&sat, &vars, None, true, |sat, assumptions, candidate| {
+            candidates.push(candidate);
+            Ok(brute_force(sat, assumptions, &mut calls))
+        })
+        .unwrap();
+        assert_eq!(backbones, Some(vec![1, -2]));
+        // Only the initial solve, c can be flipped in its model.
+        assert_eq!(candidates, vec![None]);
+    }
 }
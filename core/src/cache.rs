@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+use std::process::Command;
+
+use crate::solvers::{build_command, Solver};
+use crate::{CnfSat, EvaluationResult, Literal};
+
+/// Remembers the results of recently solved formulas. They are looked up by their fingerprint,
+/// and only used if the clauses and the variable names match as well.
+pub struct CachedSolver {
+    command_factory: Box<dyn Fn() -> Command>,
+    capacity: usize,
+    // The most recently used entry is at the back.
+    entries: VecDeque<CacheEntry>,
+}
+
+struct CacheEntry {
+    fingerprint: u64,
+    // The model of the result refers to the variables by name.
+    variable_names: Vec<String>,
+    clauses: Vec<Vec<Literal>>,
+    result: EvaluationResult,
+}
+
+impl CacheEntry {
+    fn matches(&self, fingerprint: u64, sat: &CnfSat) -> bool {
+        self.fingerprint == fingerprint
+            && self.clauses == sat.canonical_clauses()
+            && self.variable_names == sat.variable_names()
+    }
+}
+
+pub struct CachedResult {
+    pub result: EvaluationResult,
+    /// The result was taken from the cache without running the solver.
+    pub cached: bool,
+}
+
+impl CachedSolver {
+    pub fn new(solver: Solver, capacity: usize) -> CachedSolver {
+        let command_factory = move || build_command(&solver).unwrap_or_else(|err| panic!("{err}"));
+        CachedSolver::from_command_factory(command_factory, capacity)
+    }
+
+    pub fn from_command_factory(
+        command_factory: impl Fn() -> Command + 'static,
+        capacity: usize,
+    ) -> CachedSolver {
+        CachedSolver {
+            command_factory: Box::new(command_factory),
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Solve the formula unless its result is cached. Unknown and timed out results are not cached,
+    /// as another attempt may finish.
+    pub fn evaluate(&mut self, sat: &CnfSat) -> CachedResult {
+        let fingerprint = sat.fingerprint();
+
+        if let Some(index) = self.entries.iter().position(|entry| entry.matches(fingerprint, sat)) {
+            let entry = self.entries.remove(index).unwrap();
+            let result = entry.result.clone();
+            self.entries.push_back(entry);
+            return CachedResult {
+                result,
+                cached: true,
+            };
+        }
+
+        let result = sat.evaluate((self.command_factory)());
+        if self.capacity > 0 && !matches!(
+            result,
+            EvaluationResult::Unknown { .. } | EvaluationResult::Timeout { .. }
+        ) {
+            if self.entries.len() == self.capacity {
+                self.entries.pop_front();
+            }
+            self.entries.push_back(CacheEntry {
+                fingerprint,
+                variable_names: sat.variable_names(),
+                clauses: sat.canonical_clauses(),
+                result: result.clone(),
+            });
+        }
+
+        CachedResult {
+            result,
+            cached: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CnfClause;
+
+    /// A solver that counts its runs in the file and finds the first variable set.
+    #[cfg(unix)]
+    fn counting_solver(runs: &std::path::Path) -> impl Fn() -> Command + 'static {
+        let script = format!(
+            "cat > /dev/null; echo run >> '{}'; printf 's SATISFIABLE\\nv 1 0\\n'",
+            runs.display()
+        );
+        move || {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(&script);
+            command
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cache_hit_runs_no_solver() {
+        let dir = tempfile::tempdir().unwrap();
+        let runs = dir.path().join("runs");
+        let run_count = || std::fs::read_to_string(&runs).unwrap().lines().count();
+        let mut cache = CachedSolver::from_command_factory(counting_solver(&runs), 4);
+
+        let formula = |name: &str| {
+            let mut sat = CnfSat::new();
+            let id = sat.create_variable(name);
+            sat.add_clause(CnfClause::from_literals(&[(id, true)]));
+            sat
+        };
+        assert!(!cache.evaluate(&formula("a")).cached);
+        let CachedResult { result, cached } = cache.evaluate(&formula("a"));
+        assert!(cached);
+        assert_eq!(run_count(), 1);
+        let EvaluationResult::Sat { model, .. } = result else {
+            panic!("Expected a model");
+        };
+        assert_eq!(model.get_result_by_name("a"), Some(true));
+
+        // The same clauses over differently named variables have another model.
+        let CachedResult { result, cached } = cache.evaluate(&formula("b"));
+        assert!(!cached);
+        assert_eq!(run_count(), 2);
+        let EvaluationResult::Sat { model, .. } = result else {
+            panic!("Expected a model");
+        };
+        assert_eq!(model.get_result_by_name("b"), Some(true));
+    }
+}
@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use clap::Args;
+
+use crate::report::ReportFormat;
+use crate::solvers::{build_command_with, default_solver_dir, SolveOptions, Solver};
+use crate::SolverError;
+
+/// Command line options shared by all the tools.
+#[derive(Args, Debug)]
+pub struct CommonArgs {
+    /// Solver to use: auto, kissat, cadical, oxisat, oxisat-dpll, glucose, glucose-syrup,
+    /// minisat or lingeling. By default the first solver found in the solver directory.
+    pub solver: Option<String>,
+
+    /// The solver as an option, e.g. `--solver=cadical`, instead of the first argument.
+    #[arg(long = "solver", value_name = "SOLVER", conflicts_with = "solver")]
+    pub solver_option: Option<String>,
+
+    /// Number of threads for glucose-syrup.
+    pub threads: Option<String>,
+
+    /// Directory containing the solver binaries, by default $NAIL_SOLVER_DIR or ../solvers.
+    #[arg(long)]
+    pub solver_dir: Option<PathBuf>,
+
+    /// Time limit for a single solver run in seconds, enforced by the solver itself.
+    #[arg(long)]
+    pub timeout: Option<u64>,
+
+    /// Do not print progress information to stderr.
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Print the results as CSV.
+    #[arg(long)]
+    pub csv: bool,
+
+    /// Write a report of the run to this file.
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+
+    /// Format of the report, by default TOML for `.toml` files and JSON otherwise.
+    #[arg(long, value_enum)]
+    pub format: Option<ReportFormat>,
+
+    /// Print the clauses with variable names to stderr before solving.
+    #[arg(long)]
+    pub describe: bool,
+
+    /// Solve each problem this many times with seeds 0, 1, ... and print the distribution of solve times.
+    #[arg(long)]
+    pub runs: Option<usize>,
+}
+
+impl CommonArgs {
+    pub fn solve_options(&self) -> SolveOptions {
+        SolveOptions {
+            solver_dir: self.solver_dir.clone(),
+            timeout: self.timeout.map(Duration::from_secs),
+            ..Default::default()
+        }
+    }
+
+    /// The solver itself, or for [`Solver::Auto`] the one detected in the configured solver directory.
+    pub fn resolve_solver(&self, solver: &Solver) -> Result<Solver, SolverError> {
+        let solver_dir = self.solver_dir.clone().unwrap_or_else(default_solver_dir);
+        solver.resolve(&solver_dir)
+    }
+
+    /// The solver command with the configured solver directory and time limit.
+    pub fn build_command(&self, solver: &Solver) -> Result<Command, SolverError> {
+        build_command_with(solver, &self.solve_options())
+    }
+
+    /// The solver command like [`CommonArgs::build_command`], with the solver's random seed set.
+    pub fn build_command_with_seed(&self, solver: &Solver, seed: u64) -> Result<Command, SolverError> {
+        let options = SolveOptions {
+            seed: Some(seed),
+            ..self.solve_options()
+        };
+        build_command_with(solver, &options)
+    }
+}
@@ -0,0 +1,265 @@
+//! Copied from oxisat as of commit ecddd1061c60d7d1249b0a6658cd9e0fbd9c7471
+
+use std::fmt::{self, Display};
+
+use nom::error::VerboseError;
+use nom::{Finish, IResult};
+
+pub enum Literal {
+    Positive(i64),
+    Negative(i64),
+}
+
+pub struct DimacsClause {
+    literals: Vec<Literal>,
+}
+
+struct DimacsHeader {
+    variable_count: usize,
+    clause_count: usize,
+}
+
+pub struct Dimacs {
+    variable_count: usize,
+    clauses: Vec<DimacsClause>,
+}
+
+impl Dimacs {
+    pub fn variable_count(&self) -> usize {
+        self.variable_count
+    }
+
+    pub fn clauses(&self) -> &Vec<DimacsClause> {
+        &self.clauses
+    }
+}
+
+impl DimacsClause {
+    pub fn literals(&self) -> &Vec<Literal> {
+        &self.literals
+    }
+}
+
+/// Why [`parse_strict`] rejected the input.
+#[derive(Debug, PartialEq, Eq)]
+pub enum StrictError {
+    /// The input is not valid DIMACS, with a description of where parsing failed.
+    Syntax(String),
+    /// The header declares a different number of clauses than the body contains.
+    ClauseCountMismatch { declared: usize, found: usize },
+    /// A literal of the clause, counted from 0, uses a variable beyond the declared count.
+    VariableOutOfRange {
+        clause: usize,
+        variable: u64,
+        declared: usize,
+    },
+}
+
+pub fn parse(i: &str) -> IResult<&str, Dimacs, VerboseError<&str>> {
+    parser::dimacs(i)
+}
+
+/// Parse the whole input, checking the clauses against the header instead of trusting it.
+pub fn parse_strict(i: &str) -> Result<Dimacs, StrictError> {
+    let (_, (header, clauses)) = parser::dimacs_unchecked(i)
+        .finish()
+        .map_err(|err| StrictError::Syntax(nom::error::convert_error(i, err)))?;
+
+    for (index, clause) in clauses.iter().enumerate() {
+        let variable = clause
+            .literals
+            .iter()
+            .map(|literal| match literal {
+                Literal::Positive(variable) | Literal::Negative(variable) => variable.unsigned_abs(),
+            })
+            .find(|&variable| variable as usize > header.variable_count);
+        if let Some(variable) = variable {
+            return Err(StrictError::VariableOutOfRange {
+                clause: index,
+                variable,
+                declared: header.variable_count,
+            });
+        }
+    }
+
+    if clauses.len() != header.clause_count {
+        return Err(StrictError::ClauseCountMismatch {
+            declared: header.clause_count,
+            found: clauses.len(),
+        });
+    }
+
+    Ok(Dimacs {
+        variable_count: header.variable_count,
+        clauses,
+    })
+}
+
+impl Display for StrictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrictError::Syntax(description) => write!(f, "Failed to parse dimacs: {description}"),
+            StrictError::ClauseCountMismatch { declared, found } => write!(
+                f,
+                "The header declares {declared} clauses, but {found} were found"
+            ),
+            StrictError::VariableOutOfRange {
+                clause,
+                variable,
+                declared,
+            } => write!(
+                f,
+                "Clause {clause} uses variable {variable}, but the header declares only {declared} variables"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StrictError {}
+
+mod parser {
+    use super::*;
+    use nom::bytes::complete::tag;
+    use nom::character::complete::u64 as text_u64;
+    use nom::character::complete::{char, multispace0, multispace1, newline, not_line_ending, space1};
+    use nom::character::complete::{i64 as text_i64, space0};
+    use nom::combinator::{eof, value, verify};
+    use nom::error::{context, VerboseError};
+    use nom::multi::{count, many0};
+    use nom::sequence::{preceded, terminated, tuple};
+    use nom::IResult;
+
+    pub(crate) fn dimacs(i: &str) -> IResult<&str, Dimacs, VerboseError<&str>> {
+        let (i, (_, header)) = tuple((many0(comment), header))(i)?;
+
+        let (i, clauses) = count(
+            preceded(multispace1, clause(header.variable_count)),
+            header.clause_count,
+        )(i)?;
+
+        let dimacs = Dimacs {
+            variable_count: header.variable_count,
+            clauses,
+        };
+
+        Ok((i, dimacs))
+    }
+
+    /// The header and all the clauses up to the end of the input, regardless of the declared counts.
+    pub(crate) fn dimacs_unchecked(
+        i: &str,
+    ) -> IResult<&str, (DimacsHeader, Vec<DimacsClause>), VerboseError<&str>> {
+        let (i, (_, header)) = tuple((many0(comment), header))(i)?;
+        let (i, clauses) = many0(preceded(multispace1, clause(usize::MAX)))(i)?;
+        let (i, _) = context("end of input", terminated(multispace0, eof))(i)?;
+
+        Ok((i, (header, clauses)))
+    }
+
+    fn comment(i: &str) -> IResult<&str, (), VerboseError<&str>> {
+        context(
+            "comment",
+            value((), tuple((char('c'), not_line_ending, newline))),
+        )(i)
+    }
+
+    fn header(i: &str) -> IResult<&str, DimacsHeader, VerboseError<&str>> {
+        let (i, (_, _, _, _, variable_count, _, clause_count, _)) = context(
+            "header",
+            tuple((
+                tag("p"),
+                space1,
+                tag("cnf"),
+                space1,
+                text_u64,
+                space1,
+                text_u64,
+                space0,
+            )),
+        )(i)?;
+        Ok((
+            i,
+            DimacsHeader {
+                variable_count: variable_count as usize,
+                clause_count: clause_count as usize,
+            },
+        ))
+    }
+
+    fn clause(
+        max_variable: usize,
+    ) -> impl Fn(&str) -> IResult<&str, DimacsClause, VerboseError<&str>> {
+        move |i: &str| {
+            // Ensure that the clauses do not contain variables with numbers too high.
+            let literal = verify(text_i64, |&literal: &i64| {
+                literal != 0 && literal.unsigned_abs() as usize <= max_variable
+            });
+
+            // Clauses are inconveniently terminated by a single 0, and may span multiple lines.
+            let (i, (literals, _)) = tuple((many0(terminated(literal, multispace1)), char('0')))(i)?;
+
+            let literals = literals
+                .iter()
+                .map(|&var| {
+                    if var < 0 {
+                        Literal::Negative(-var)
+                    } else {
+                        Literal::Positive(var)
+                    }
+                })
+                .collect();
+
+            Ok((i, DimacsClause { literals }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dimacs_literals(dimacs: &Dimacs) -> Vec<Vec<i64>> {
+        dimacs
+            .clauses()
+            .iter()
+            .map(|clause| {
+                clause
+                    .literals()
+                    .iter()
+                    .map(|literal| match literal {
+                        Literal::Positive(variable) => *variable,
+                        Literal::Negative(variable) => -variable,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clauses_span_lines() {
+        let input = "c comment\np cnf 3 2\n1 -2\n  3 0 -1\n\n2 0\n";
+        let (_, dimacs) = parse(input).finish().unwrap();
+        assert_eq!(dimacs.variable_count(), 3);
+        assert_eq!(dimacs_literals(&dimacs), vec![vec![1, -2, 3], vec![-1, 2]]);
+    }
+
+    #[test]
+    fn strict_parse_checks_header() {
+        let dimacs = parse_strict("p cnf 2 2\n1 -2 0\n2 0\n").unwrap();
+        assert_eq!(dimacs_literals(&dimacs), vec![vec![1, -2], vec![2]]);
+
+        assert_eq!(
+            parse_strict("p cnf 2 3\n1 -2 0\n2 0\n").err(),
+            Some(StrictError::ClauseCountMismatch { declared: 3, found: 2 })
+        );
+        assert_eq!(
+            parse_strict("p cnf 2 1\n1 0\n2 0\n").err(),
+            Some(StrictError::ClauseCountMismatch { declared: 1, found: 2 })
+        );
+        assert_eq!(
+            parse_strict("p cnf 2 2\n1 0\n-3 2 0\n").err(),
+            Some(StrictError::VariableOutOfRange { clause: 1, variable: 3, declared: 2 })
+        );
+        assert!(matches!(parse_strict("p cnf x\n"), Err(StrictError::Syntax(_))));
+    }
+}
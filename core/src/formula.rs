@@ -0,0 +1,125 @@
+use crate::{CnfClause, CnfSat, Literal, VarId};
+
+/// A boolean formula over the variables of a [`CnfSat`], added to it by [`CnfSat::add_formula`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Formula {
+    Var(VarId),
+    Not(Box<Formula>),
+    /// True when all the subformulas are, so an empty conjunction is true.
+    And(Vec<Formula>),
+    /// True when any of the subformulas is, so an empty disjunction is false.
+    Or(Vec<Formula>),
+}
+
+impl Formula {
+    /// Tseitin transformation: every conjunction and disjunction gets an auxiliary variable
+    /// equivalent to it, so the clauses grow linearly with the size of the formula.
+    /// Returns the literal equivalent to the whole formula.
+    pub(crate) fn encode(&self, sat: &mut CnfSat) -> Literal {
+        match self {
+            Formula::Var(id) => (*id, true),
+            Formula::Not(formula) => {
+                let (id, value) = formula.encode(sat);
+                (id, !value)
+            }
+            Formula::And(formulas) | Formula::Or(formulas) if formulas.len() == 1 => {
+                formulas[0].encode(sat)
+            }
+            Formula::And(formulas) => {
+                // t ⇔ l1 ∧ ... ∧ ln is (¬t ∨ l1) ∧ ... ∧ (¬t ∨ ln) ∧ (t ∨ ¬l1 ∨ ... ∨ ¬ln)
+                let literals: Vec<_> = formulas.iter().map(|formula| formula.encode(sat)).collect();
+                let gate = sat.create_auxiliary_variable("and");
+                add_and_gate(sat, (gate, true), &literals);
+                (gate, true)
+            }
+            Formula::Or(formulas) => {
+                // t ⇔ l1 ∨ ... ∨ ln is the same as ¬t ⇔ ¬l1 ∧ ... ∧ ¬ln
+                let negated: Vec<_> = formulas
+                    .iter()
+                    .map(|formula| {
+                        let (id, value) = formula.encode(sat);
+                        (id, !value)
+                    })
+                    .collect();
+                let gate = sat.create_auxiliary_variable("or");
+                add_and_gate(sat, (gate, false), &negated);
+                (gate, true)
+            }
+        }
+    }
+}
+
+/// Clauses making the gate literal equivalent to the conjunction of the literals.
+fn add_and_gate(sat: &mut CnfSat, (gate, gate_value): Literal, literals: &[Literal]) {
+    let mut long_clause = CnfClause::new();
+    long_clause.set(gate, gate_value);
+    for &(id, value) in literals {
+        let mut clause = CnfClause::new();
+        clause.set(gate, !gate_value);
+        clause.set(id, value);
+        sat.add_clause(clause);
+
+        long_clause.set(id, !value);
+    }
+    sat.add_clause(long_clause);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_holds_exactly_when;
+
+    /// The value of the formula under values indexed by variable id.
+    fn value_of(formula: &Formula, values: &[bool]) -> bool {
+        match formula {
+            Formula::Var(id) => values[id.0],
+            Formula::Not(formula) => !value_of(formula, values),
+            Formula::And(formulas) => formulas.iter().all(|formula| value_of(formula, values)),
+            Formula::Or(formulas) => formulas.iter().any(|formula| value_of(formula, values)),
+        }
+    }
+
+    #[test]
+    fn and_gate_truth_table() {
+        for gate_value in [false, true] {
+            for (a_value, b_value) in [(true, true), (true, false), (false, false)] {
+                let mut sat = CnfSat::new();
+                let gate = sat.create_variable("gate");
+                let a = sat.create_variable("a");
+                let b = sat.create_variable("b");
+                add_and_gate(&mut sat, (gate, gate_value), &[(a, a_value), (b, b_value)]);
+                assert_holds_exactly_when(&sat, &[gate, a, b], |values| {
+                    (values[0] == gate_value) == (values[1] == a_value && values[2] == b_value)
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn formulas_match_their_truth_tables() {
+        let var = |id| Formula::Var(VarId(id));
+        let not = |formula| Formula::Not(Box::new(formula));
+        let formulas = [
+            var(0),
+            not(var(1)),
+            Formula::And(vec![]),
+            Formula::Or(vec![]),
+            Formula::And(vec![var(0), not(var(1))]),
+            Formula::Or(vec![var(0), var(1), var(2)]),
+            // Exactly one of three, written out
+            Formula::And(vec![
+                Formula::Or(vec![var(0), var(1), var(2)]),
+                not(Formula::And(vec![var(0), var(1)])),
+                not(Formula::And(vec![var(0), var(2)])),
+                not(Formula::And(vec![var(1), var(2)])),
+            ]),
+            not(Formula::Or(vec![Formula::And(vec![var(0), var(1)]), not(var(2))])),
+        ];
+        for formula in formulas {
+            let mut sat = CnfSat::new();
+            let v: Vec<_> = ["a", "b", "c"].iter().map(|name| sat.create_variable(name)).collect();
+            sat.add_formula(&formula);
+            assert_holds_exactly_when(&sat, &v, |values| value_of(&formula, values));
+        }
+    }
+}
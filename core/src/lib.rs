@@ -1,87 +1,866 @@
+pub mod cache;
+pub mod cli;
+pub mod dimacs;
+pub mod formula;
+pub mod report;
 pub mod solvers;
 
-use std::collections::HashMap;
-use std::fmt::Write as _;
-use std::io::Write;
-use std::process::{Command, Stdio};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display, Write as _};
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStderr, Command, ExitStatus, Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::{mem, thread};
 use std::time::{Duration, Instant};
 
-#[derive(PartialEq, Eq, Hash)]
+use nom::Finish;
+use serde::Serialize;
+use tempfile::NamedTempFile;
+
+use crate::formula::Formula;
+use crate::solvers::{
+    binary_name, build_command_with, default_solver_dir, SatSolver, SolveOptions, Solver,
+    SolverInterface, SOLVER_DIR_ENV,
+};
+
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct SatVariable {
     name: String,
-    id: usize,
+    id: VarId,
 }
 
+#[derive(Clone)]
 pub struct CnfClause {
-    values: HashMap<usize, bool>,
+    // Literals in the order they were set, each literal at most once.
+    // A variable appears with both polarities only in a tautology.
+    values: Vec<Literal>,
+    // The same literals for constant time lookups.
+    index: HashSet<Literal>,
+    // Whether some variable appears with both polarities.
+    tautology: bool,
+    // Clause groups that were open when the clause was added.
+    groups: Vec<GroupId>,
+}
+
+/// Handle of a group of clauses that can be removed together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GroupId(usize);
+
+/// State of a formula to return to with [`CnfSat::restore`].
+#[derive(Clone)]
+pub struct Checkpoint {
+    variable_count: usize,
+    clauses: Vec<CnfClause>,
+    soft_clauses: Vec<SoftClause>,
+}
+
+/// Id of a variable, its index in the order of creation. DIMACS numbers variables from 1 instead,
+/// see [`VarId::from_dimacs`] and [`VarId::dimacs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct VarId(pub usize);
+
+/// A literal given as a variable id and the value it asserts.
+pub type Literal = (VarId, bool);
+
+/// Longest XOR constraint encoded without auxiliary variables, see [`CnfSat::ensure_xor`].
+const XOR_DIRECT_MAX: usize = 4;
+
+/// Preferences are optimized after all user tiers.
+const PREFERENCE_TIER: usize = usize::MAX;
+
+#[derive(Clone)]
+pub struct SoftClause {
+    clause: CnfClause,
+    weight: u64,
+    tier: usize,
 }
 
+#[derive(Clone)]
 pub struct CnfSat {
-    variables: HashMap<String, SatVariable>,
+    // Indexed by id.
+    variables: Vec<SatVariable>,
+    variable_ids: HashMap<String, VarId>,
     clauses: Vec<CnfClause>,
+    soft_clauses: Vec<SoftClause>,
+    open_groups: Vec<GroupId>,
+    next_group: usize,
+    literal_order: ClauseLiteralOrder,
 }
 
+#[derive(Clone, Serialize)]
 pub struct SatModel {
     results_by_name: HashMap<String, bool>,
-    results_by_id: HashMap<usize, bool>,
+    // Ordered, so that iteration follows the ids.
+    results_by_id: BTreeMap<VarId, bool>,
+    #[serde(skip)]
+    names_by_id: HashMap<VarId, String>,
 }
 
+/// The `solve_time` is the wall clock time of the solver process from its start, including reading
+/// the input. The `total_time` also includes generating the input and parsing the output.
+/// The `solver` is the name of the solver's binary, empty if the answer was found without running it.
+#[derive(Clone)]
 pub enum EvaluationResult {
-    Sat { dimacs: String, model: SatModel, time: Duration },
-    Unsat { dimacs: String, time: Duration },
+    Sat { dimacs: String, model: SatModel, solver: String, solve_time: Duration, total_time: Duration },
+    /// The `core` holds the assumptions the formula is unsatisfiable with, empty when solved without any.
+    /// The solvers take assumptions as unit clauses and print no final conflict, so it has all of them;
+    /// [`CnfSat::failed_assumptions`] shrinks it to a minimal subset.
+    Unsat {
+        dimacs: String,
+        core: Vec<Literal>,
+        solver: String,
+        solve_time: Duration,
+        total_time: Duration,
+    },
+    /// The solver gave up, e.g. after reaching its time limit.
+    Unknown { dimacs: String, solve_time: Duration, total_time: Duration },
+    /// The solver was stopped by a signal sent on timeout, by a wrapper, the system's CPU limit
+    /// or [`CnfSat::evaluate_with_timeout`].
+    Timeout { dimacs: String, solve_time: Duration, total_time: Duration },
+}
+
+/// Answer of a MaxSAT solver, see [`CnfSat::optimization_result_from_dimacs`].
+#[derive(Clone)]
+pub enum OptimizationResult {
+    /// The cost is the total weight of the soft clauses the model violates, as given by the solver.
+    Optimum { cost: u64, model: SatModel },
+    /// The hard clauses cannot be satisfied together.
+    Unsat,
+    /// The solver stopped before proving any model optimal.
+    Unknown,
+}
+
+/// Failure to get an answer from the solver process.
+#[derive(Debug)]
+pub enum SolverError {
+    /// The solver binary exists but lacks the execute permission.
+    NotExecutable { path: PathBuf },
+    /// The solver process could not be started.
+    SpawnFailed { path: PathBuf, source: io::Error },
+    /// Writing the formula to the solver's input failed, e.g. because it exited early.
+    WriteFailed(io::Error),
+    /// Waiting for the solver or reading its output failed.
+    WaitFailed(io::Error),
+    /// The solver's output is not valid UTF-8.
+    NonUtf8Output(std::string::FromUtf8Error),
+    /// The solver failed without giving an answer. The code is missing if it was killed by a signal.
+    NonZeroExit { code: Option<i32>, stderr: String },
+    /// The solver found the formula satisfiable but printed no `v` lines with the model.
+    MissingModel,
+    /// [`Solver::Auto`] found none of the solvers in the solver directory.
+    NoSolverFound { solver_dir: PathBuf },
+    /// The solver gave up or was stopped on the formula under these assumptions,
+    /// so a search that needs the answer cannot tell the result.
+    Undecided { assumptions: Vec<Literal> },
+}
+
+/// Why the solver's output contains no model.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ModelError {
+    Unsatisfiable,
+    /// Satisfiable, but there are no `v` lines with the model.
+    MissingModel,
+    /// No `s SATISFIABLE` or `s UNSATISFIABLE` line, e.g. `s UNKNOWN` or `s INDETERMINATE`
+    /// from a solver that reached its limit.
+    Undecided,
+}
+
+/// Failure to read a formula in DIMACS.
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    /// The input is not valid DIMACS, with a description of where parsing failed.
+    Syntax(String),
+}
+
+/// Why a formula is unsatisfiable without any search.
+#[derive(Debug, PartialEq, Eq)]
+pub enum UnsatReason {
+    /// The clause at this index has no literals.
+    EmptyClause(usize),
+    /// Unit clauses force this variable to be both true and false.
+    ContradictoryUnits(VarId),
+}
+
+/// Values given to variables a model leaves unassigned when a complete assignment is needed.
+#[derive(Debug, Clone, Default)]
+pub enum ModelFillPolicy {
+    #[default]
+    AllFalse,
+    AllTrue,
+    /// Values taken from the map, variables missing from it are false.
+    FromMap(HashMap<VarId, bool>),
+}
+
+/// Order of literals within each clause in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClauseLiteralOrder {
+    /// The order in which the literals were set.
+    InsertionOrder,
+    /// Ascending by variable id, which makes the output deterministic.
+    #[default]
+    AscendingVar,
+    /// Negative literals before positive ones, each ascending by variable id.
+    NegativeFirst,
+}
+
+/// How at-most-one constraints are turned into clauses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AtMostOneEncoding {
+    /// A binary clause for every pair of variables, no auxiliary variables.
+    #[default]
+    Pairwise,
+    /// Pairwise constraints within consecutive windows of the given size, chained by carry variables.
+    /// Each window also includes the carry of the previous one, which is true when any earlier variable is.
+    Ladder { window: usize },
+}
+
+/// Variant of the WCNF format for MaxSAT solvers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WcnfVersion {
+    /// `p wcnf` header, hard clauses marked by the top weight.
+    Legacy,
+    /// No header, hard clauses marked by `h`, as used by MaxSAT evaluations since 2022.
+    #[default]
+    Modern,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantifier {
+    Exists,
+    ForAll,
 }
 
+/// Changes between two formulas, clauses compared in canonical form.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FormulaDiff {
+    pub added_clauses: Vec<Vec<Literal>>,
+    pub removed_clauses: Vec<Vec<Literal>>,
+    pub added_variables: Vec<String>,
+}
+
+impl FormulaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_clauses.is_empty()
+            && self.removed_clauses.is_empty()
+            && self.added_variables.is_empty()
+    }
+}
+
+/// Outcome of [`CnfSat::propagate_units`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PropagationResult {
+    /// Literals fixed by unit clauses, in the order they were found.
+    pub assignments: Vec<Literal>,
+    /// Whether a clause became false, which makes the formula unsatisfiable.
+    pub conflict: bool,
+}
+
+/// Overview of a formula produced by [`CnfSat::analyze`].
+#[derive(Serialize)]
+pub struct FormulaAnalysis {
+    /// SAT, UNSAT, UNKNOWN or TIMEOUT.
+    pub status: String,
+    pub model: Option<SatModel>,
+    pub backbones: Vec<Literal>,
+    pub component_count: usize,
+    pub variable_count: usize,
+    pub clause_count: usize,
+    pub solver_calls: usize,
+}
+
+/// Size of a formula produced by [`CnfSat::stats`]. Only hard clauses are counted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CnfStats {
+    pub variable_count: usize,
+    pub clause_count: usize,
+    /// Literals summed over all clauses.
+    pub literal_count: usize,
+    pub unit_clause_count: usize,
+    /// Zero for a formula without clauses, like the median and maximum.
+    pub mean_clause_length: f64,
+    pub median_clause_length: f64,
+    pub max_clause_length: usize,
+}
+
+/// Solve times of repeated runs on the same formula.
+#[derive(Debug)]
+pub struct TimingDistribution {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+}
+
+impl VarId {
+    /// The variable numbered `number` in DIMACS, which counts from 1.
+    pub fn from_dimacs(number: u64) -> VarId {
+        VarId(number as usize - 1)
+    }
+
+    /// The variable's number in DIMACS, counting from 1.
+    pub fn dimacs(self) -> u64 {
+        self.0 as u64 + 1
+    }
+}
+
+impl SatVariable {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn id(&self) -> VarId {
+        self.id
+    }
+}
+
+impl Default for CnfClause {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl CnfClause {
     pub fn new() -> CnfClause {
         CnfClause {
-            values: HashMap::new(),
+            values: Vec::new(),
+            index: HashSet::new(),
+            tautology: false,
+            groups: Vec::new(),
+        }
+    }
+
+    /// A clause of the given literals, as if each was added by [`CnfClause::set`] in order,
+    /// e.g. `CnfClause::from_literals(&[(a, true), (b, false)])` for a ∨ ¬b.
+    pub fn from_literals(literals: &[Literal]) -> CnfClause {
+        let mut clause = CnfClause::new();
+        for &(id, value) in literals {
+            clause.set(id, value);
+        }
+        clause
+    }
+
+    /// Add the literal to the clause. Setting a variable already present with the opposite value
+    /// keeps both literals, which makes the clause a tautology, see [`CnfClause::is_tautology`].
+    pub fn set(&mut self, variable_id: VarId, value: bool) {
+        if self.index.insert((variable_id, value)) {
+            self.values.push((variable_id, value));
+            self.tautology |= self.index.contains(&(variable_id, !value));
         }
     }
 
-    pub fn set(&mut self, variable_id: usize, value: bool) {
-        self.values.insert(variable_id, value);
+    /// Whether the clause contains a variable with both values, so it is satisfied by every assignment.
+    pub fn is_tautology(&self) -> bool {
+        self.tautology
+    }
+
+    /// The value the clause has for the variable, if it contains it.
+    /// For a variable set to both values, the one set first.
+    pub fn get(&self, variable_id: VarId) -> Option<bool> {
+        match (
+            self.index.contains(&(variable_id, true)),
+            self.index.contains(&(variable_id, false)),
+        ) {
+            (true, true) => self
+                .values
+                .iter()
+                .find(|(id, _)| *id == variable_id)
+                .map(|&(_, value)| value),
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            (false, false) => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Literal> + '_ {
+        self.values.iter().copied()
+    }
+
+    /// The literals of the clause sorted by variable id, a canonical form for comparing clauses.
+    pub fn literals(&self) -> Vec<Literal> {
+        let mut literals: Vec<_> = self.iter().collect();
+        literals.sort_unstable();
+        literals
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Keep only the literals the predicate holds for, in their order.
+    fn retain_literals(&mut self, mut keep: impl FnMut(Literal) -> bool) {
+        let index = &mut self.index;
+        self.values.retain(|&literal| {
+            let kept = keep(literal);
+            if !kept {
+                index.remove(&literal);
+            }
+            kept
+        });
+        if self.tautology {
+            let index = &self.index;
+            self.tautology = self.values.iter().any(|&(id, value)| index.contains(&(id, !value)));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl Default for CnfSat {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl CnfSat {
     pub fn new() -> CnfSat {
+        CnfSat::with_capacity(0, 0)
+    }
+
+    /// An empty formula with room for the given numbers of variables and clauses,
+    /// avoiding reallocations while building a formula of a known size.
+    pub fn with_capacity(variables: usize, clauses: usize) -> CnfSat {
         CnfSat {
-            variables: HashMap::new(),
-            clauses: Vec::new(),
+            variables: Vec::with_capacity(variables),
+            variable_ids: HashMap::with_capacity(variables),
+            clauses: Vec::with_capacity(clauses),
+            soft_clauses: Vec::new(),
+            open_groups: Vec::new(),
+            next_group: 0,
+            literal_order: ClauseLiteralOrder::default(),
+        }
+    }
+
+    /// Read a formula in DIMACS. Variables are named by their 1-based DIMACS index,
+    /// so their ids are the DIMACS index minus one.
+    pub fn from_dimacs(input: &str) -> Result<CnfSat, ParseError> {
+        let dimacs = match dimacs::parse(input).finish() {
+            Ok((_, dimacs)) => dimacs,
+            Err(err) => return Err(ParseError::Syntax(nom::error::convert_error(input, err))),
+        };
+
+        let mut sat = CnfSat::with_capacity(dimacs.variable_count(), dimacs.clauses().len());
+        for variable in 1..=dimacs.variable_count() {
+            sat.create_variable(&variable.to_string());
+        }
+        for dimacs_clause in dimacs.clauses() {
+            let mut clause = CnfClause::new();
+            for literal in dimacs_clause.literals() {
+                match literal {
+                    dimacs::Literal::Positive(variable) => clause.set(VarId::from_dimacs(*variable as u64), true),
+                    dimacs::Literal::Negative(variable) => clause.set(VarId::from_dimacs(*variable as u64), false),
+                };
+            }
+            sat.add_clause(clause);
         }
+
+        Ok(sat)
+    }
+
+    /// Read a formula from a DIMACS file, see [`CnfSat::from_dimacs`].
+    pub fn from_dimacs_file(path: &Path) -> Result<CnfSat, ParseError> {
+        let input = fs::read_to_string(path).map_err(ParseError::Io)?;
+        CnfSat::from_dimacs(&input)
     }
 
-    pub fn create_variable(&mut self, name: &str) {
-        if self.variables.contains_key(name) {
+    /// Create a variable with a unique name and return its id. Ids are assigned sequentially from 0.
+    pub fn create_variable(&mut self, name: &str) -> VarId {
+        if self.variable_ids.contains_key(name) {
             panic!("The variable name has to be unique.");
         }
         let variable = SatVariable {
             name: name.to_string(),
-            id: self.variables.len(),
+            id: VarId(self.variables.len()),
         };
-        self.variables.insert(name.to_string(), variable);
+        let id = variable.id;
+        self.variable_ids.insert(name.to_string(), id);
+        self.variables.push(variable);
+        id
     }
 
-    pub fn add_clause(&mut self, clause: CnfClause) {
+    /// Create a variable for each of the names, see [`CnfSat::create_variable`].
+    /// The ids are consecutive and returned in the order of the names.
+    pub fn create_variables(&mut self, names: impl IntoIterator<Item = String>) -> Vec<VarId> {
+        let names = names.into_iter();
+        let (additional, _) = names.size_hint();
+        self.variables.reserve(additional);
+        self.variable_ids.reserve(additional);
+        names.map(|name| self.create_variable(&name)).collect()
+    }
+
+    pub fn add_clause(&mut self, mut clause: CnfClause) {
+        clause.groups = self.open_groups.clone();
         self.clauses.push(clause);
     }
 
+    /// Open a new group. All clauses added until the group is closed or removed belong to it.
+    /// Groups may be nested, a clause then belongs to all open groups.
+    pub fn add_group(&mut self) -> GroupId {
+        let id = GroupId(self.next_group);
+        self.next_group += 1;
+        self.open_groups.push(id);
+        id
+    }
+
+    /// Stop adding new clauses to the group, keeping the clauses it already has.
+    pub fn close_group(&mut self, id: GroupId) {
+        self.open_groups.retain(|&group| group != id);
+    }
+
+    /// Remove exactly the clauses added to the group, regardless of what else was added since.
+    pub fn remove_group(&mut self, id: GroupId) {
+        self.close_group(id);
+        self.clauses.retain(|clause| !clause.groups.contains(&id));
+    }
+
     /// Remove the last added clause.
     pub fn pop_clause(&mut self) -> Option<CnfClause> {
         self.clauses.pop()
     }
 
-    pub fn get_variable_by_id(&self, id: usize) -> Option<&SatVariable> {
-        // TODO: This is not particularly effective.
-        let (_, var) = self.variables.iter().find(|(_, var)| var.id == id)?;
-        Some(var)
+    /// Remember the current variables and clauses, see [`CnfSat::restore`].
+    /// The clauses are copied, as they may be removed or replaced before the restore.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            variable_count: self.variables.len(),
+            clauses: self.clauses.clone(),
+            soft_clauses: self.soft_clauses.clone(),
+        }
+    }
+
+    /// Return to the clauses and soft clauses of the checkpoint and remove the variables created since,
+    /// even on an early return that would skip a matching [`CnfSat::pop_clause`]. Clauses removed since,
+    /// e.g. by [`CnfSat::remove_group`] or [`CnfSat::prefer`], are brought back.
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        for variable in self.variables.drain(checkpoint.variable_count.min(self.variables.len())..) {
+            self.variable_ids.remove(&variable.name);
+        }
+        self.clauses = checkpoint.clauses;
+        self.soft_clauses = checkpoint.soft_clauses;
+    }
+
+    /// Remove clauses with the same literals as an earlier clause, regardless of their order.
+    /// Copies belonging to different groups are kept, so that removing a group keeps its meaning.
+    /// Returns the number of removed clauses.
+    pub fn dedup_clauses(&mut self) -> usize {
+        let count = self.clauses.len();
+        let mut seen = HashSet::new();
+        self.clauses
+            .retain(|clause| seen.insert((clause.literals(), clause.groups.clone())));
+        count - self.clauses.len()
+    }
+
+    /// Apply the unit clauses until there are no new ones: every clause satisfied by a fixed variable
+    /// is removed and false literals are removed from the others. A single unit clause is kept for
+    /// each fixed variable, so the formula stays equivalent and models still assign it.
+    ///
+    /// Stops at the first conflict, leaving the empty clause or the contradictory unit clauses
+    /// in the formula, see [`CnfSat::trivially_unsat`]. Soft clauses are left unchanged, and clauses
+    /// shortened by a unit clause from a group stay shortened after the group is removed.
+    pub fn propagate_units(&mut self) -> PropagationResult {
+        let mut values: HashMap<VarId, bool> = HashMap::new();
+        let mut result = PropagationResult::default();
+
+        loop {
+            let mut changed = false;
+            for clause in &self.clauses {
+                if let [(id, value)] = clause.values[..] {
+                    match values.get(&id) {
+                        None => {
+                            values.insert(id, value);
+                            result.assignments.push((id, value));
+                            changed = true;
+                        }
+                        Some(&fixed) if fixed != value => result.conflict = true,
+                        Some(_) => {}
+                    }
+                }
+            }
+
+            let mut kept_units = HashSet::new();
+            self.clauses.retain_mut(|clause| {
+                if let [(id, value)] = clause.values[..] {
+                    // Keep the first unit clause of each variable and any contradicting one.
+                    return values[&id] != value || kept_units.insert(id);
+                }
+                if clause.iter().any(|(id, value)| values.get(&id) == Some(&value)) {
+                    return false;
+                }
+                clause.retain_literals(|(id, _)| !values.contains_key(&id));
+                true
+            });
+            result.conflict |= self.clauses.iter().any(|clause| clause.is_empty());
+
+            if result.conflict || !changed {
+                return result;
+            }
+        }
+    }
+
+    /// Remove every clause whose literals are a superset of another clause's literals.
+    /// Such clauses are implied by the smaller clause, so satisfiability is preserved.
+    /// Like in [`CnfSat::dedup_clauses`], a clause is only removed for one that belongs to no other
+    /// groups than it does, so that removing a group keeps its meaning.
+    /// Returns the number of removed clauses.
+    ///
+    /// Comparing every pair of clauses takes O(clauses²) subset checks. Instead, each kept clause is
+    /// indexed under its literal that occurs in the fewest clauses, and a clause is only compared to
+    /// the kept clauses indexed under one of its own literals. The worst case stays quadratic, but
+    /// typical encodings need far fewer checks.
+    pub fn eliminate_subsumed(&mut self) -> usize {
+        let canonical: Vec<_> = self.clauses.iter().map(|clause| clause.literals()).collect();
+
+        let mut occurrences: HashMap<Literal, usize> = HashMap::new();
+        let mut by_length: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (i, literals) in canonical.iter().enumerate() {
+            for &literal in literals {
+                *occurrences.entry(literal).or_default() += 1;
+            }
+            by_length.entry(literals.len()).or_default().push(i);
+        }
+
+        // Going from the shortest clauses, a clause can only be subsumed by one that was kept before it.
+        // A subsuming clause's index literal is in the subsumed clause, so the index finds it.
+        let mut kept_by_literal: HashMap<Literal, Vec<usize>> = HashMap::new();
+        let mut kept_count = 0;
+        let mut kept_empty = Vec::new();
+        let mut subsumed = vec![false; canonical.len()];
+        for i in by_length.into_values().flatten() {
+            let literals = &canonical[i];
+            let groups = &self.clauses[i].groups;
+            let subsumes = |k: usize| {
+                is_sorted_subset(&canonical[k], literals)
+                    && self.clauses[k].groups.iter().all(|group| groups.contains(group))
+            };
+            let is_subsumed = kept_empty.iter().any(|&k| subsumes(k))
+                || literals.iter().any(|literal| {
+                    kept_by_literal
+                        .get(literal)
+                        .is_some_and(|kept| kept.iter().any(|&k| subsumes(k)))
+                });
+            if is_subsumed {
+                subsumed[i] = true;
+                continue;
+            }
+
+            kept_count += 1;
+            match literals.iter().min_by_key(|literal| occurrences[literal]) {
+                Some(&literal) => kept_by_literal.entry(literal).or_default().push(i),
+                None => kept_empty.push(i),
+            }
+        }
+
+        let mut index = 0;
+        self.clauses.retain(|_| {
+            index += 1;
+            !subsumed[index - 1]
+        });
+
+        canonical.len() - kept_count
+    }
+
+    /// Check for an empty clause or a pair of unit clauses with opposite literals.
+    pub fn trivially_unsat(&self) -> Option<UnsatReason> {
+        if let Some(index) = self.clauses.iter().position(|clause| clause.is_empty()) {
+            return Some(UnsatReason::EmptyClause(index));
+        }
+
+        let mut units = HashMap::new();
+        for clause in self.clauses.iter().filter(|clause| clause.len() == 1) {
+            for (id, value) in clause.iter() {
+                if *units.entry(id).or_insert(value) != value {
+                    return Some(UnsatReason::ContradictoryUnits(id));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Literals of the unit clauses, each once, ordered by variable id.
+    /// Every model satisfies them, so they are backbones of a satisfiable formula.
+    pub fn unit_literals(&self) -> Vec<Literal> {
+        let mut units: Vec<_> = self
+            .clauses
+            .iter()
+            .filter(|clause| clause.len() == 1)
+            .flat_map(|clause| clause.iter())
+            .collect();
+        units.sort_unstable();
+        units.dedup();
+        units
+    }
+
+    /// Whether the formula is unsatisfiable without any search, see [`CnfSat::trivially_unsat`].
+    pub fn is_trivially_unsat(&self) -> bool {
+        self.trivially_unsat().is_some()
+    }
+
+    /// Variables that do not appear in any clause, hard or soft, usually a sign of a missing constraint.
+    pub fn unused_variables(&self) -> Vec<VarId> {
+        let mut used = vec![false; self.variables.len()];
+        let soft = self.soft_clauses.iter().map(|soft| &soft.clause);
+        for clause in self.clauses.iter().chain(soft) {
+            for (id, _) in clause.iter() {
+                if let Some(used) = used.get_mut(id.0) {
+                    *used = true;
+                }
+            }
+        }
+        (0..used.len()).filter(|&id| !used[id]).map(VarId).collect()
+    }
+
+    /// Split the hard clauses into independent sub-formulas that share no variables.
+    ///
+    /// Each component is returned with the original ids of its variables, indexed by the
+    /// component's ids. Variables appearing in no clause belong to no component; empty clauses
+    /// form a component of their own without variables. Clauses may use ids beyond the created
+    /// variables, the component names such a variable `#<id>`.
+    pub fn components(&self) -> Vec<(CnfSat, Vec<VarId>)> {
+        // Union-find over variables, joining all variables of each clause.
+        let variable_count = self
+            .max_referenced_variable()
+            .map_or(0, |id| id.0 + 1)
+            .max(self.variables.len());
+        let mut parents: Vec<usize> = (0..variable_count).collect();
+        fn find(parents: &mut [usize], mut x: usize) -> usize {
+            while parents[x] != x {
+                parents[x] = parents[parents[x]];
+                x = parents[x];
+            }
+            x
+        }
+        for clause in &self.clauses {
+            let mut literals = clause.iter();
+            if let Some((first, _)) = literals.next() {
+                for (other, _) in literals {
+                    let (a, b) = (find(&mut parents, first.0), find(&mut parents, other.0));
+                    parents[a] = b;
+                }
+            }
+        }
+
+        // Group clauses by the root of their variables, keeping the order of the first appearance.
+        let mut component_of_root: HashMap<Option<usize>, usize> = HashMap::new();
+        let mut clause_groups: Vec<Vec<&CnfClause>> = Vec::new();
+        for clause in &self.clauses {
+            let root = clause.iter().next().map(|(id, _)| find(&mut parents, id.0));
+            let index = *component_of_root.entry(root).or_insert_with(|| {
+                clause_groups.push(Vec::new());
+                clause_groups.len() - 1
+            });
+            clause_groups[index].push(clause);
+        }
+
+        clause_groups
+            .into_iter()
+            .map(|clauses| {
+                let mut variables: Vec<_> = clauses
+                    .iter()
+                    .flat_map(|clause| clause.iter().map(|(id, _)| id))
+                    .collect();
+                variables.sort_unstable();
+                variables.dedup();
+
+                let mut sat = CnfSat::new();
+                let mut new_ids = HashMap::new();
+                for &id in &variables {
+                    let name = match self.get_variable_by_id(id) {
+                        Some(variable) => variable.name.clone(),
+                        None => format!("#{}", id.0),
+                    };
+                    new_ids.insert(id, sat.create_variable(&name));
+                }
+                for clause in clauses {
+                    let mut new_clause = CnfClause::new();
+                    for (id, value) in clause.iter() {
+                        new_clause.set(new_ids[&id], value);
+                    }
+                    sat.add_clause(new_clause);
+                }
+
+                (sat, variables)
+            })
+            .collect()
+    }
+
+    /// What has to change to get from this formula to `other`. Clause order does not matter,
+    /// but duplicate clauses are counted.
+    pub fn diff(&self, other: &CnfSat) -> FormulaDiff {
+        let mut counts: BTreeMap<Vec<Literal>, isize> = BTreeMap::new();
+        for clause in &self.clauses {
+            *counts.entry(clause.literals()).or_default() -= 1;
+        }
+        for clause in &other.clauses {
+            *counts.entry(clause.literals()).or_default() += 1;
+        }
+
+        let mut diff = FormulaDiff::default();
+        for (literals, count) in counts {
+            let target = if count > 0 {
+                &mut diff.added_clauses
+            } else {
+                &mut diff.removed_clauses
+            };
+            for _ in 0..count.unsigned_abs() {
+                target.push(literals.clone());
+            }
+        }
+
+        diff.added_variables = other
+            .variable_ids
+            .keys()
+            .filter(|name| !self.variable_ids.contains_key(*name))
+            .cloned()
+            .collect();
+        diff.added_variables.sort_unstable();
+
+        diff
+    }
+
+    /// Hash of the variable count and the clauses in canonical form.
+    /// It does not depend on the order of clauses or of literals within them.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.variables.len().hash(&mut hasher);
+        self.canonical_clauses().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The clauses with their literals sorted, in sorted order.
+    pub(crate) fn canonical_clauses(&self) -> Vec<Vec<Literal>> {
+        let mut clauses: Vec<_> = self.clauses.iter().map(|clause| clause.literals()).collect();
+        clauses.sort_unstable();
+        clauses
+    }
+
+    /// Names of the variables by id.
+    pub(crate) fn variable_names(&self) -> Vec<String> {
+        self.variables.iter().map(|variable| variable.name.clone()).collect()
     }
-    pub fn get_variable(&self, name: &str) -> usize {
-        self.variables[name].id
+
+    pub fn get_variable_by_id(&self, id: VarId) -> Option<&SatVariable> {
+        self.variables.get(id.0)
+    }
+    /// Id of the variable with the name, panics if there is none.
+    pub fn get_variable(&self, name: &str) -> VarId {
+        self.try_get_variable(name)
+            .unwrap_or_else(|| panic!("Unknown variable {name}"))
+    }
+
+    pub fn try_get_variable(&self, name: &str) -> Option<VarId> {
+        self.variable_ids.get(name).copied()
     }
 
-    pub fn ensure_at_least_one_set(&mut self, variables: &[usize]) {
+    pub fn ensure_at_least_one_set(&mut self, variables: &[VarId]) {
         // At least one variable is chosen is simply encoded as
         // v1 ∨ v2 ∨ ... ∨ vN
         let mut clause = CnfClause::new();
@@ -91,148 +870,3185 @@ impl CnfSat {
         self.add_clause(clause);
     }
 
-    pub fn ensure_max_one_set(&mut self, variables: &[usize]) {
+    pub fn ensure_max_one_set(&mut self, variables: &[VarId]) {
         // At most one variable is chosen is encoded as "There is no pair of variables that are both true"
         // That is ∀ v1, v2: ¬v1 ∨ ¬v2
         for (i, variable1) in variables.iter().enumerate() {
             for variable2 in variables[i + 1..].iter() {
-                let mut pair_clause = CnfClause::new();
-                pair_clause.set(*variable1, false);
-                pair_clause.set(*variable2, false);
-                self.add_clause(pair_clause);
+                self.add_clause(CnfClause::from_literals(&[(*variable1, false), (*variable2, false)]));
             }
         }
     }
 
-    pub fn variable_count(&self) -> usize {
-        self.variables.len()
+    pub fn ensure_exactly_one_set(&mut self, variables: &[VarId]) {
+        self.ensure_at_least_one_set(variables);
+        self.ensure_max_one_set(variables);
     }
 
-    pub fn clause_count(&self) -> usize {
-        self.clauses.len()
+    /// The formula holds. It is turned into clauses by the Tseitin transformation,
+    /// which adds an auxiliary variable for every conjunction and disjunction.
+    pub fn add_formula(&mut self, formula: &Formula) {
+        let literal = formula.encode(self);
+        self.add_clause(CnfClause::from_literals(&[literal]));
     }
 
-    // DIMACS
-    //   line oriented
-    // c comment
-    // p cnf <#variables> <#clauses>
-    // index1 -index2 index3 index4 -index5
-    pub fn to_dimacs(&self) -> String {
-        let mut dimacs = String::new();
-        let _ = writeln!(
-            dimacs,
-            "p cnf {} {}",
-            self.variables.len(),
-            self.clauses.len()
-        );
-        for clause in &self.clauses {
-            let values = clause
-                .values
-                .iter()
-                .map(|(id, value)| {
-                    if *value {
-                        format!("{}", id + 1)
-                    } else {
-                        format!("-{}", id + 1)
-                    }
-                })
-                .collect::<Vec<String>>()
-                .join(" ");
+    /// If `a` is set, so is `b`: ¬a ∨ b
+    pub fn ensure_implies(&mut self, a: VarId, b: VarId) {
+        self.add_clause(CnfClause::from_literals(&[(a, false), (b, true)]));
+    }
+
+    /// `a` and `b` have the same value: (¬a ∨ b) ∧ (a ∨ ¬b)
+    pub fn ensure_iff(&mut self, a: VarId, b: VarId) {
+        self.ensure_implies(a, b);
+        self.ensure_implies(b, a);
+    }
 
-            let _ = writeln!(dimacs, "{} 0", values);
+    /// Exactly one variable is set in every row and every column of the square grid,
+    /// so the set variables describe a permutation.
+    pub fn ensure_permutation(&mut self, grid: &[Vec<VarId>]) {
+        let n = grid.len();
+        if grid.iter().any(|row| row.len() != n) {
+            panic!("The permutation grid has to be square.");
+        }
+
+        for row in grid {
+            self.ensure_exactly_one_set(row);
+        }
+        for x in 0..n {
+            let column: Vec<_> = grid.iter().map(|row| row[x]).collect();
+            self.ensure_exactly_one_set(&column);
         }
-        dimacs
     }
 
-    pub fn result_from_dimacs(&self, dimacs: &str) -> Result<SatModel, ()> {
-        let mut satisfiable = false;
-        let mut model = Vec::new();
-        for line in dimacs.lines() {
-            if line.starts_with('s') {
-                satisfiable = !line.contains("UNSATISFIABLE")
-            }
-            if line.starts_with('v') {
-                let model_description = line.trim()[2..].split_whitespace();
-                for literal in model_description {
-                    let val: i64 = literal.parse().unwrap();
-                    if val == 0 {
-                        continue;
-                    }
+    /// Exactly one of the groups is active and exactly one variable of the active group is set,
+    /// while all variables of inactive groups are unset. Returns the indicator variables
+    /// of the groups, true exactly for the active group.
+    pub fn ensure_exactly_one_group_exactly_one(&mut self, groups: &[Vec<VarId>]) -> Vec<VarId> {
+        let indicators: Vec<_> = groups
+            .iter()
+            .map(|_| self.create_auxiliary_variable("group_indicator"))
+            .collect();
+        self.ensure_exactly_one_set(&indicators);
 
-                    let id = val.unsigned_abs() as usize - 1;
-                    let set_true = val > 0;
-                    model.push((id, set_true));
-                }
+        for (group, &indicator) in groups.iter().zip(&indicators) {
+            // The indicator is equivalent to the group having a set variable.
+            let mut some_set = CnfClause::new();
+            some_set.set(indicator, false);
+            for &variable in group {
+                some_set.set(variable, true);
+
+                let mut implies_indicator = CnfClause::new();
+                implies_indicator.set(variable, false);
+                implies_indicator.set(indicator, true);
+                self.add_clause(implies_indicator);
             }
-        }
+            self.add_clause(some_set);
 
-        if satisfiable {
-            Ok(SatModel::from_vec(self, &model))
-        } else {
-            Err(())
+            self.ensure_max_one_set(group);
         }
+
+        indicators
     }
 
-    pub fn evaluate(&self, mut solver_command: Command) -> EvaluationResult {
-        let mut solver = solver_command
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()
-            .expect("Failed to run solver");
+    pub fn ensure_max_one_set_with(&mut self, variables: &[VarId], encoding: AtMostOneEncoding) {
+        match encoding {
+            AtMostOneEncoding::Pairwise => self.ensure_max_one_set(variables),
+            AtMostOneEncoding::Ladder { window } => {
+                assert!(window > 0, "The ladder window has to be non-empty.");
 
-        let input = self.to_dimacs();
-        solver
-            .stdin
-            .as_mut()
-            .expect("Failed to use glucose's stdin")
-            .write_all(input.as_bytes())
-            .expect("Failed to write glucose's input");
+                let chunk_count = variables.len().div_ceil(window);
+                let mut carry = None;
+                for (i, chunk) in variables.chunks(window).enumerate() {
+                    let mut group = chunk.to_vec();
+                    group.extend(carry);
+                    self.ensure_max_one_set(&group);
 
-        let start_time = Instant::now();
-        let output = solver
-            .wait_with_output()
-            .expect("Failed to get output from glucose");
-        let elapsed_time = start_time.elapsed();
+                    if i + 1 < chunk_count {
+                        // The carry is implied by every variable of this window and by the previous carry.
+                        let next_carry = self.create_auxiliary_variable("ladder_carry");
+                        for &variable in &group {
+                            let mut clause = CnfClause::new();
+                            clause.set(variable, false);
+                            clause.set(next_carry, true);
+                            self.add_clause(clause);
+                        }
+                        carry = Some(next_carry);
+                    }
+                }
+            }
+        }
+    }
 
-        let dimacs_output = String::from_utf8(output.stdout).expect("Non-UTF8 output from glucose");
+    /// At most `k` of the variables are set, encoded with a sequential counter that needs
+    /// O(n·k) auxiliary variables and clauses instead of the O(n^(k+1)) of forbidding every subset.
+    pub fn ensure_at_most_k(&mut self, variables: &[VarId], k: usize) {
+        let literals: Vec<_> = variables.iter().map(|&id| (id, true)).collect();
+        self.ensure_at_most_k_literals(&literals, k);
+    }
 
-        match self.result_from_dimacs(&dimacs_output) {
-            Ok(model) => EvaluationResult::Sat {
-                dimacs: dimacs_output,
-                model,
-                time: elapsed_time,
-            },
-            Err(_) => EvaluationResult::Unsat {
-                dimacs: dimacs_output,
-                time: elapsed_time,
-            },
+    /// At least `k` of the variables are set, as at most `n - k` of them being unset.
+    /// More than `n` adds an empty clause, making the formula unsatisfiable.
+    pub fn ensure_at_least_k(&mut self, variables: &[VarId], k: usize) {
+        if k > variables.len() {
+            self.add_clause(CnfClause::new());
+            return;
         }
+        let unset: Vec<_> = variables.iter().map(|&id| (id, false)).collect();
+        self.ensure_at_most_k_literals(&unset, variables.len() - k);
     }
-}
 
-impl SatModel {
-    pub fn from_vec(sat: &CnfSat, model: &Vec<(usize, bool)>) -> SatModel {
-        let mut results_by_name = HashMap::new();
-        let mut results_by_id = HashMap::new();
+    /// Exactly `k` of the variables are set.
+    pub fn ensure_exactly_k(&mut self, variables: &[VarId], k: usize) {
+        self.ensure_at_most_k(variables, k);
+        self.ensure_at_least_k(variables, k);
+    }
 
-        for (id, value) in model {
-            let name = sat.get_variable_by_id(*id).unwrap().name.to_string();
-            results_by_id.insert(*id, *value);
-            results_by_name.insert(name, *value);
+    /// An odd number of the variables is set if `value` is true, an even number otherwise.
+    ///
+    /// Up to four variables are encoded directly by forbidding every assignment
+    /// of the wrong parity, which takes 2^(n-1) clauses. Longer constraints are split into a chain:
+    /// an auxiliary variable takes the parity of a few variables and replaces them in the rest.
+    pub fn ensure_xor(&mut self, variables: &[VarId], value: bool) {
+        let mut rest = variables.to_vec();
+        while rest.len() > XOR_DIRECT_MAX {
+            let mut chunk: Vec<_> = rest.drain(..XOR_DIRECT_MAX - 1).collect();
+            let parity = self.create_auxiliary_variable("xor");
+            // parity = x1 ⊕ x2 ⊕ x3 is the same as x1 ⊕ x2 ⊕ x3 ⊕ parity being false.
+            chunk.push(parity);
+            self.ensure_xor_direct(&chunk, false);
+            rest.push(parity);
         }
-        SatModel {
-            results_by_name,
-            results_by_id,
+        self.ensure_xor_direct(&rest, value);
+    }
+
+    fn ensure_xor_direct(&mut self, variables: &[VarId], value: bool) {
+        for assignment in 0u64..1 << variables.len() {
+            let odd = assignment.count_ones() % 2 == 1;
+            if odd == value {
+                continue;
+            }
+            let mut clause = CnfClause::new();
+            for (i, &variable) in variables.iter().enumerate() {
+                clause.set(variable, assignment & (1 << i) == 0);
+            }
+            self.add_clause(clause);
         }
     }
 
-    pub fn get_result_by_id(&self, id: usize) -> Option<bool> {
+    /// Models differ from `reference` in at most `distance` of the given variables.
+    ///
+    /// The literal opposite to the reference value already indicates that a variable differs,
+    /// so the cardinality constraint is placed on these literals without extra XOR variables.
+    pub fn ensure_within_hamming(&mut self, reference: &SatModel, variables: &[VarId], distance: usize) {
+        let differs: Vec<_> = variables
+            .iter()
+            .map(|&id| {
+                let value = reference
+                    .get_result_by_id(id)
+                    .expect("The reference model has to assign all the variables");
+                (id, !value)
+            })
+            .collect();
+        self.ensure_at_most_k_literals(&differs, distance);
+    }
+
+    /// At most `k` of the literals are true, using the sequential counter encoding (Sinz, 2005).
+    /// Counter variable `s[i][j]` is true when at least `j + 1` of the first `i + 1` literals are.
+    fn ensure_at_most_k_literals(&mut self, literals: &[Literal], k: usize) {
+        let n = literals.len();
+        if k >= n {
+            return;
+        }
+        let negated = |(id, value): Literal| (id, !value);
+        let add = |sat: &mut CnfSat, clause_literals: &[Literal]| {
+            sat.add_clause(CnfClause::from_literals(clause_literals));
+        };
+
+        if k == 0 {
+            for &literal in literals {
+                add(self, &[negated(literal)]);
+            }
+            return;
+        }
+
+        let counters: Vec<Vec<VarId>> = (0..n - 1)
+            .map(|_| (0..k).map(|_| self.create_auxiliary_variable("counter")).collect())
+            .collect();
+
+        add(self, &[negated(literals[0]), (counters[0][0], true)]);
+        for &counter in &counters[0][1..] {
+            add(self, &[(counter, false)]);
+        }
+        for i in 1..n - 1 {
+            add(self, &[negated(literals[i]), (counters[i][0], true)]);
+            add(self, &[(counters[i - 1][0], false), (counters[i][0], true)]);
+            for j in 1..k {
+                add(
+                    self,
+                    &[negated(literals[i]), (counters[i - 1][j - 1], false), (counters[i][j], true)],
+                );
+                add(self, &[(counters[i - 1][j], false), (counters[i][j], true)]);
+            }
+            // Overflow: the literal is true while k earlier literals are already true.
+            add(self, &[negated(literals[i]), (counters[i - 1][k - 1], false)]);
+        }
+        add(self, &[negated(literals[n - 1]), (counters[n - 2][k - 1], false)]);
+    }
+
+    /// Create a variable for use inside an encoding, named so that it does not collide with user variables.
+    fn create_auxiliary_variable(&mut self, purpose: &str) -> VarId {
+        let mut name = format!("__{}_{}", purpose, self.variables.len());
+        while self.variable_ids.contains_key(&name) {
+            name.push('_');
+        }
+        self.create_variable(&name)
+    }
+
+    /// Ids of all the variables, in the order of creation.
+    pub fn all_variables(&self) -> impl Iterator<Item = VarId> {
+        (0..self.variables.len()).map(VarId)
+    }
+
+    pub fn variable_count(&self) -> usize {
+        self.variables.len()
+    }
+
+    pub fn clause_count(&self) -> usize {
+        self.clauses.len()
+    }
+
+    /// Counts and clause lengths for a quick look at the size of an encoding.
+    pub fn stats(&self) -> CnfStats {
+        let mut lengths: Vec<_> = self.clauses.iter().map(|clause| clause.len()).collect();
+        lengths.sort_unstable();
+
+        let count = lengths.len();
+        let literal_count: usize = lengths.iter().sum();
+        let median_clause_length = match count {
+            0 => 0.0,
+            _ if count % 2 == 1 => lengths[count / 2] as f64,
+            _ => (lengths[count / 2 - 1] + lengths[count / 2]) as f64 / 2.0,
+        };
+
+        CnfStats {
+            variable_count: self.variables.len(),
+            clause_count: count,
+            literal_count,
+            unit_clause_count: lengths.iter().filter(|&&length| length == 1).count(),
+            mean_clause_length: if count == 0 { 0.0 } else { literal_count as f64 / count as f64 },
+            median_clause_length,
+            max_clause_length: lengths.last().copied().unwrap_or(0),
+        }
+    }
+
+    /// The highest variable id used in any clause, hard or soft.
+    /// [`CnfClause::set`] accepts any id, so this may exceed the created variables.
+    pub fn max_referenced_variable(&self) -> Option<VarId> {
+        let soft = self.soft_clauses.iter().map(|soft| &soft.clause);
+        self.clauses
+            .iter()
+            .chain(soft)
+            .flat_map(|clause| clause.iter().map(|(id, _)| id))
+            .max()
+    }
+
+    /// Variable count for output headers, never less than what the clauses reference.
+    fn declared_variable_count(&self) -> usize {
+        let referenced = self.max_referenced_variable().map_or(0, |id| id.0 + 1);
+        self.variables.len().max(referenced)
+    }
+
+    /// Order of literals within clauses in all outputs, including the solver input.
+    pub fn set_literal_order(&mut self, order: ClauseLiteralOrder) {
+        self.literal_order = order;
+    }
+
+    fn ordered_literals(&self, clause: &CnfClause) -> Vec<Literal> {
+        let mut literals = clause.values.clone();
+        match self.literal_order {
+            ClauseLiteralOrder::InsertionOrder => {}
+            ClauseLiteralOrder::AscendingVar => literals.sort_unstable(),
+            ClauseLiteralOrder::NegativeFirst => {
+                literals.sort_unstable_by_key(|&(id, value)| (value, id))
+            }
+        }
+        literals
+    }
+
+    /// One clause per line with variable names, e.g. `(queen_x1_y2_q0 ∨ ¬queen_x3_y4_q1)`.
+    pub fn to_readable(&self) -> String {
+        let mut description = String::new();
+        for clause in &self.clauses {
+            let literals: Vec<_> = self
+                .ordered_literals(clause)
+                .into_iter()
+                .map(|(id, value)| {
+                    let name = match self.get_variable_by_id(id) {
+                        Some(variable) => variable.name.clone(),
+                        None => format!("#{}", id.0),
+                    };
+                    if value {
+                        name
+                    } else {
+                        format!("¬{}", name)
+                    }
+                })
+                .collect();
+            let _ = writeln!(description, "({})", literals.join(" ∨ "));
+        }
+        description
+    }
+
+    // DIMACS
+    //   line oriented
+    // c comment
+    // p cnf <#variables> <#clauses>
+    // index1 -index2 index3 index4 -index5
+    pub fn to_dimacs(&self) -> String {
+        self.to_dimacs_with_units(&[])
+    }
+
+    /// DIMACS of the formula with extra unit clauses appended after the stored ones.
+    fn to_dimacs_with_units(&self, units: &[Literal]) -> String {
+        let mut dimacs = Vec::new();
+        self.write_dimacs_with_units(&mut dimacs, units)
+            .expect("Writing to a Vec cannot fail");
+        String::from_utf8(dimacs).expect("DIMACS is always valid UTF-8")
+    }
+
+    /// Write the DIMACS clause by clause, without building it in memory first.
+    /// Every stored clause is written, tautologies included, like in all other outputs,
+    /// so the header always agrees with [`CnfSat::clause_count`].
+    pub fn write_dimacs<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.write_dimacs_with_units(writer, &[])
+    }
+
+    /// Save the formula as a DIMACS file, which [`CnfSat::from_dimacs_file`] reads back.
+    pub fn write_dimacs_to_path(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_dimacs(&mut writer)?;
+        writer.flush()
+    }
+
+    fn write_dimacs_with_units<W: Write>(&self, writer: &mut W, units: &[Literal]) -> io::Result<()> {
+        let max_unit = units.iter().map(|&(id, _)| id.0 + 1).max().unwrap_or(0);
+        writeln!(
+            writer,
+            "p cnf {} {}",
+            self.declared_variable_count().max(max_unit),
+            self.clauses.len() + units.len()
+        )?;
+        for clause in &self.clauses {
+            writeln!(writer, "{} 0", dimacs_literals(self.ordered_literals(clause)))?;
+        }
+        for &unit in units {
+            writeln!(writer, "{} 0", dimacs_literals([unit]))?;
+        }
+        Ok(())
+    }
+
+    // QDIMACS
+    //   DIMACS with a quantifier prefix between the header and the clauses
+    // a index1 index2 0
+    // e index3 0
+    /// QDIMACS with the given quantifier prefix, outermost block first.
+    /// Variables missing from the prefix are existentially quantified in the innermost block.
+    pub fn to_qdimacs(&self, quantifier_prefix: &[(Quantifier, Vec<VarId>)]) -> String {
+        let mut quantified = vec![false; self.variables.len()];
+        for (_, variables) in quantifier_prefix {
+            for &VarId(id) in variables {
+                if id >= self.variables.len() {
+                    panic!("The quantifier prefix contains an undeclared variable {}.", id);
+                }
+                if quantified[id] {
+                    panic!("The variable {} is quantified more than once.", id);
+                }
+                quantified[id] = true;
+            }
+        }
+
+        let mut prefix: Vec<_> = quantifier_prefix
+            .iter()
+            .filter(|(_, variables)| !variables.is_empty())
+            .map(|(quantifier, variables)| (*quantifier, variables.clone()))
+            .collect();
+        let free: Vec<_> = (0..self.variables.len())
+            .filter(|&id| !quantified[id])
+            .map(VarId)
+            .collect();
+        if !free.is_empty() {
+            match prefix.last_mut() {
+                Some((Quantifier::Exists, innermost)) => innermost.extend(free),
+                _ => prefix.push((Quantifier::Exists, free)),
+            }
+        }
+
+        let mut qdimacs = String::new();
+        let _ = writeln!(
+            qdimacs,
+            "p cnf {} {}",
+            self.declared_variable_count(),
+            self.clauses.len()
+        );
+        for (quantifier, variables) in prefix {
+            let symbol = match quantifier {
+                Quantifier::Exists => 'e',
+                Quantifier::ForAll => 'a',
+            };
+            let literals = dimacs_literals(variables.into_iter().map(|id| (id, true)));
+            let _ = writeln!(qdimacs, "{} {} 0", symbol, literals);
+        }
+        for clause in &self.clauses {
+            let _ = writeln!(qdimacs, "{} 0", dimacs_literals(self.ordered_literals(clause)));
+        }
+        qdimacs
+    }
+
+    /// A soft clause the MaxSAT solver tries to satisfy, unlike the hard ones added by [`CnfSat::add_clause`].
+    /// The solver minimizes the total weight of the violated soft clauses.
+    pub fn add_soft_clause(&mut self, clause: CnfClause, weight: u64) {
+        self.add_soft_clause_tiered(clause, weight, 0);
+    }
+
+    /// Soft clauses are kept apart from the hard ones and only appear in the WCNF output.
+    /// Tiers are optimized lexicographically, tier 0 first.
+    pub fn add_soft_clause_tiered(&mut self, clause: CnfClause, weight: u64, tier: usize) {
+        self.soft_clauses.push(SoftClause {
+            clause,
+            weight,
+            tier,
+        });
+    }
+
+    /// Prefer the variable to have the given value when the constraints allow either.
+    /// The preference is a soft unit clause in the last tier, below all other soft clauses;
+    /// a later preference for the same variable replaces the earlier one.
+    pub fn prefer(&mut self, id: VarId, value: bool) {
+        self.soft_clauses
+            .retain(|soft| soft.tier != PREFERENCE_TIER || soft.clause.get(id).is_none());
+
+        let mut clause = CnfClause::new();
+        clause.set(id, value);
+        self.add_soft_clause_tiered(clause, 1, PREFERENCE_TIER);
+    }
+
+    pub fn preference(&self, id: VarId) -> Option<bool> {
+        self.soft_clauses
+            .iter()
+            .filter(|soft| soft.tier == PREFERENCE_TIER)
+            .find_map(|soft| soft.clause.get(id))
+    }
+
+    // WCNF
+    //   like DIMACS with a weight before every clause
+    // Legacy format, hard clauses have the top weight:
+    // p wcnf <#variables> <#clauses> <top>
+    // weight index1 -index2 index3 0
+    // Modern format (MaxSAT evaluations since 2022), no header:
+    // h index1 -index2 0
+    // weight index3 0
+    /// WCNF with the soft clauses weighted as given, ignoring their tiers.
+    pub fn to_wcnf(&self, version: WcnfVersion) -> String {
+        let weights: Vec<_> = self.soft_clauses.iter().map(|soft| soft.weight).collect();
+        self.to_wcnf_with_weights(version, &weights)
+    }
+
+    /// WCNF for a single-objective MaxSAT solver in which the tiers are optimized lexicographically.
+    ///
+    /// Weights are scaled so that violating a clause in some tier costs more than violating
+    /// all clauses of all later tiers together; tier 0 therefore takes precedence over tier 1 and so on.
+    /// Within a tier the weights add up as usual, and models with equal costs in every tier are
+    /// equally optimal, so the solver may return any of them.
+    pub fn to_wcnf_lex(&self, version: WcnfVersion) -> String {
+        let mut tiers: Vec<_> = self.soft_clauses.iter().map(|soft| soft.tier).collect();
+        tiers.sort_unstable();
+        tiers.dedup();
+
+        let mut multipliers = HashMap::new();
+        let mut lower_tiers_total: u64 = 0;
+        for &tier in tiers.iter().rev() {
+            let multiplier = lower_tiers_total + 1;
+            let tier_total = self
+                .soft_clauses
+                .iter()
+                .filter(|soft| soft.tier == tier)
+                .map(|soft| soft.weight.checked_mul(multiplier))
+                .try_fold(0u64, |total, weight| total.checked_add(weight?))
+                .expect("Scaled soft clause weights overflow u64");
+            multipliers.insert(tier, multiplier);
+            lower_tiers_total = lower_tiers_total
+                .checked_add(tier_total)
+                .expect("Scaled soft clause weights overflow u64");
+        }
+
+        let weights: Vec<_> = self
+            .soft_clauses
+            .iter()
+            .map(|soft| soft.weight * multipliers[&soft.tier])
+            .collect();
+        self.to_wcnf_with_weights(version, &weights)
+    }
+
+    /// WCNF with the given weights of the soft clauses.
+    fn to_wcnf_with_weights(&self, version: WcnfVersion, weights: &[u64]) -> String {
+        let mut wcnf = String::new();
+        let soft_clauses = self.soft_clauses.iter().zip(weights);
+        match version {
+            WcnfVersion::Legacy => {
+                // The top weight is greater than the cost of violating all soft clauses together.
+                let top = weights
+                    .iter()
+                    .try_fold(1u64, |total, &weight| total.checked_add(weight))
+                    .expect("Soft clause weights overflow u64");
+
+                let _ = writeln!(
+                    wcnf,
+                    "p wcnf {} {} {}",
+                    self.declared_variable_count(),
+                    self.clauses.len() + self.soft_clauses.len(),
+                    top
+                );
+                for clause in &self.clauses {
+                    let _ = writeln!(wcnf, "{} {} 0", top, dimacs_literals(self.ordered_literals(clause)));
+                }
+                for (soft, weight) in soft_clauses {
+                    let _ = writeln!(wcnf, "{} {} 0", weight, dimacs_literals(self.ordered_literals(&soft.clause)));
+                }
+            }
+            WcnfVersion::Modern => {
+                for clause in &self.clauses {
+                    let _ = writeln!(wcnf, "h {} 0", dimacs_literals(self.ordered_literals(clause)));
+                }
+                for (soft, weight) in soft_clauses {
+                    let _ = writeln!(wcnf, "{} {} 0", weight, dimacs_literals(self.ordered_literals(&soft.clause)));
+                }
+            }
+        }
+        wcnf
+    }
+
+    /// The model from the solver's output, or why there is none.
+    pub fn result_from_dimacs(&self, dimacs: &str) -> Result<SatModel, ModelError> {
+        let mut status = None;
+        let mut has_values = false;
+        let mut model = Vec::new();
+        let mut model_complete = false;
+        for line in dimacs.lines() {
+            if let Some(answer) = line.strip_prefix("s ") {
+                status = Some(answer.trim());
+            }
+            // The model may span many `v` lines, separated by any whitespace, and ends with a 0.
+            if let Some(values) = line.strip_prefix('v') {
+                has_values = true;
+                if model_complete {
+                    continue;
+                }
+                for literal in values.split_whitespace() {
+                    let val: i64 = literal.parse().unwrap();
+                    if val == 0 {
+                        model_complete = true;
+                        break;
+                    }
+
+                    let id = VarId::from_dimacs(val.unsigned_abs());
+                    let set_true = val > 0;
+                    model.push((id, set_true));
+                }
+            }
+        }
+
+        match status {
+            Some("SATISFIABLE") if !has_values && self.variable_count() > 0 => Err(ModelError::MissingModel),
+            Some("SATISFIABLE") => Ok(SatModel::from_vec(self, &model)),
+            Some("UNSATISFIABLE") => Err(ModelError::Unsatisfiable),
+            _ => Err(ModelError::Undecided),
+        }
+    }
+
+    /// The answer in the output of a MaxSAT solver for the WCNF of the formula.
+    /// The last `o` line gives the cost, `s OPTIMUM FOUND` marks the model as optimal. The model
+    /// is either a list of literals or a single string of 0s and 1s, one for each variable,
+    /// as printed in MaxSAT evaluations since 2022.
+    pub fn optimization_result_from_dimacs(&self, dimacs: &str) -> OptimizationResult {
+        let mut status = None;
+        let mut cost = None;
+        let mut model = Vec::new();
+        for line in dimacs.lines() {
+            if let Some(value) = line.strip_prefix('s') {
+                status = Some(value.trim());
+            } else if let Some(value) = line.strip_prefix('o') {
+                // Solvers print every improvement, the last one is the best.
+                cost = value.trim().parse::<u64>().ok();
+            } else if let Some(values) = line.strip_prefix('v') {
+                let values: Vec<_> = values.split_whitespace().collect();
+                match values[..] {
+                    [bits] if bits.len() == self.variable_count() && bits.chars().all(|c| c == '0' || c == '1') => {
+                        model.extend(bits.chars().enumerate().map(|(id, bit)| (VarId(id), bit == '1')));
+                    }
+                    _ => {
+                        for literal in values {
+                            let val: i64 = literal.parse().unwrap();
+                            if val == 0 {
+                                continue;
+                            }
+                            model.push((VarId::from_dimacs(val.unsigned_abs()), val > 0));
+                        }
+                    }
+                }
+            }
+        }
+
+        match (status, cost) {
+            (Some("OPTIMUM FOUND"), Some(cost)) => OptimizationResult::Optimum {
+                cost,
+                model: SatModel::from_vec(self, &model),
+            },
+            (Some("UNSATISFIABLE"), _) => OptimizationResult::Unsat,
+            _ => OptimizationResult::Unknown,
+        }
+    }
+
+    pub fn evaluate(&self, solver_command: Command) -> EvaluationResult {
+        self.run_solver(solver_command, &[])
+    }
+
+    /// Solve with any solver backend, see [`SatSolver`].
+    pub fn evaluate_on(&self, solver: &dyn SatSolver) -> EvaluationResult {
+        solver.solve(self)
+    }
+
+    /// Like [`CnfSat::evaluate`], but failing to run the solver or read its answer is returned as an error.
+    pub fn try_evaluate(&self, solver_command: Command) -> Result<EvaluationResult, SolverError> {
+        self.try_run_solver(solver_command, &[])
+    }
+
+    /// Check that the model satisfies every clause, without running the solver.
+    /// Returns the index of the first clause it does not satisfy. Variables the model leaves
+    /// unassigned satisfy nothing, except in tautologies, which hold under any assignment.
+    pub fn verify_model(&self, model: &SatModel) -> Result<(), usize> {
+        let violated = self.clauses.iter().position(|clause| {
+            !clause.is_tautology()
+                && !clause
+                    .iter()
+                    .any(|(id, value)| model.get_result_by_id(id) == Some(value))
+        });
+        match violated {
+            Some(index) => Err(index),
+            None => Ok(()),
+        }
+    }
+
+    /// Variables the model assigns whose value can be flipped without falsifying any clause,
+    /// because every clause they satisfy has another true literal. None of them is a backbone.
+    /// Only created variables are returned, even if the clauses use ids beyond them.
+    pub fn rotatable_variables(&self, model: &SatModel) -> Vec<VarId> {
+        let variable_count = self
+            .max_referenced_variable()
+            .map_or(0, |id| id.0 + 1)
+            .max(self.variables.len());
+        let mut critical = vec![false; variable_count];
+        for clause in self.clauses.iter().filter(|clause| !clause.is_tautology()) {
+            let mut true_literals = clause
+                .iter()
+                .filter(|&(id, value)| model.get_result_by_id(id) == Some(value));
+            if let (Some((id, _)), None) = (true_literals.next(), true_literals.next()) {
+                critical[id.0] = true;
+            }
+        }
+        self.all_variables()
+            .filter(|&id| !critical[id.0] && model.get_result_by_id(id).is_some())
+            .collect()
+    }
+
+    /// For every variable assigned by the model, whether some other model gives it the opposite value.
+    /// Variables that cannot be flipped are exactly the backbones. Every model found while testing
+    /// a variable also settles all the other variables it flips.
+    ///
+    /// Fails with [`SolverError::Undecided`] if the solver gives up on a variable.
+    pub fn flippable_variables(
+        &self,
+        command_factory: impl Fn() -> Command,
+        model: &SatModel,
+    ) -> Result<HashMap<VarId, bool>, SolverError> {
+        let mut flippable: HashMap<VarId, bool> = model
+            .results_by_id
+            .keys()
+            .map(|&id| (id, false))
+            .collect();
+
+        let mut ids: Vec<_> = flippable.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            if flippable[&id] {
+                continue;
+            }
+
+            let value = model.results_by_id[&id];
+            let result = self.try_decide(command_factory(), &[(id, !value)])?;
+            if let EvaluationResult::Sat { model: other, .. } = result {
+                for (other_id, can_flip) in flippable.iter_mut() {
+                    if other.get_result_by_id(*other_id) == Some(!model.results_by_id[other_id]) {
+                        *can_flip = true;
+                    }
+                }
+            }
+        }
+
+        Ok(flippable)
+    }
+
+    /// A model giving `flip` the opposite value than `model` does, or `None` if there is none,
+    /// meaning `flip` is a backbone. The other variables may differ from `model` as well.
+    /// If `model` leaves `flip` unassigned, any model of the formula differs from it.
+    ///
+    /// Fails with [`SolverError::Undecided`] if the solver gives up.
+    pub fn neighbor_model(
+        &self,
+        command_factory: impl Fn() -> Command,
+        model: &SatModel,
+        flip: VarId,
+    ) -> Result<Option<SatModel>, SolverError> {
+        let assumptions: Vec<_> = model
+            .get_result_by_id(flip)
+            .map(|value| (flip, !value))
+            .into_iter()
+            .collect();
+        match self.try_decide(command_factory(), &assumptions)? {
+            EvaluationResult::Sat { model, .. } => Ok(Some(model)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Like [`CnfSat::evaluate`], but the solver is killed once it runs longer than `timeout`,
+    /// giving a `Timeout` result with the time until it was killed.
+    ///
+    /// The input is written and the output read by separate threads, so a solver that stops
+    /// reading its input early cannot block the wait. When the solver is killed, its output
+    /// is not waited for, as processes it started may still hold the pipes open; the `Timeout`
+    /// result has the output read until then.
+    pub fn evaluate_with_timeout(&self, solver_command: Command, timeout: Duration) -> EvaluationResult {
+        if let Some(result) = self.trivial_result() {
+            return result;
+        }
+
+        let started = Instant::now();
+        let input = self.to_dimacs();
+        let solver_name = solver_name(&solver_command);
+        let mut solver = spawn_solver(solver_command).unwrap_or_else(|err| panic!("{err}"));
+        let mut stdin = solver.stdin.take().expect("Failed to use glucose's stdin");
+        let mut stdout = solver.stdout.take().expect("Failed to use glucose's stdout");
+        let stderr = solver.stderr.take().expect("Failed to use glucose's stderr");
+
+        let start_time = Instant::now();
+        // The solver may exit without reading everything, so a failed write is not an error.
+        thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let stdout_so_far = Arc::new(Mutex::new(Vec::new()));
+        let stdout_reader = thread::spawn({
+            let stdout_so_far = Arc::clone(&stdout_so_far);
+            move || -> io::Result<()> {
+                let mut buffer = [0; 8192];
+                loop {
+                    let read = stdout.read(&mut buffer)?;
+                    if read == 0 {
+                        return Ok(());
+                    }
+                    stdout_so_far.lock().unwrap().extend_from_slice(&buffer[..read]);
+                }
+            }
+        });
+        let stderr_reader = tee_stderr(stderr);
+
+        let status = loop {
+            if let Some(status) = solver.try_wait().expect("Failed to wait for glucose") {
+                break status;
+            }
+            let elapsed_time = start_time.elapsed();
+            if elapsed_time >= timeout {
+                // The solver may have exited in the meantime, then there is nothing to kill.
+                let _ = solver.kill();
+                let _ = solver.wait();
+                return EvaluationResult::Timeout {
+                    dimacs: String::from_utf8_lossy(&stdout_so_far.lock().unwrap()).into_owned(),
+                    solve_time: elapsed_time,
+                    total_time: started.elapsed(),
+                };
+            }
+            thread::sleep((timeout - elapsed_time).min(Duration::from_millis(10)));
+        };
+        let elapsed_time = start_time.elapsed();
+
+        stdout_reader
+            .join()
+            .unwrap()
+            .expect("Failed to get output from glucose");
+        let output = Output {
+            status,
+            stdout: mem::take(&mut *stdout_so_far.lock().unwrap()),
+            stderr: stderr_reader.join().unwrap(),
+        };
+        self.result_from_output(output, solver_name, elapsed_time, started)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Solve with the solver configured by the options.
+    /// File-based solvers get the formula in a temporary file, see [`CnfSat::try_evaluate_with_files`].
+    /// The result names the solver even when it is run through a wrapper.
+    pub fn evaluate_with(&self, solver: &Solver, options: &SolveOptions) -> EvaluationResult {
+        let solver_dir = options.solver_dir.clone().unwrap_or_else(default_solver_dir);
+        let solver = &solver.resolve(&solver_dir).unwrap_or_else(|err| panic!("{err}"));
+        let mut result = if solver.interface() == SolverInterface::Files {
+            self.try_evaluate_with_files(solver, options)
+                .unwrap_or_else(|err| panic!("{err}"))
+        } else {
+            let command = build_command_with(solver, options).unwrap_or_else(|err| panic!("{err}"));
+            if options.stream {
+                self.evaluate_streaming(command)
+            } else {
+                self.evaluate(command)
+            }
+        };
+
+        if !options.wrapper.is_empty() {
+            if let EvaluationResult::Sat { solver: name, .. } | EvaluationResult::Unsat { solver: name, .. } =
+                &mut result
+            {
+                *name = binary_name(solver).to_string();
+            }
+        }
+        result
+    }
+
+    /// Run a file-based solver, see [`SolverInterface::Files`]. The DIMACS is written to a temporary
+    /// input file and the answer read back from a temporary result file, both removed afterwards.
+    /// The input and result files in the options are replaced.
+    pub fn try_evaluate_with_files(
+        &self,
+        solver: &Solver,
+        options: &SolveOptions,
+    ) -> Result<EvaluationResult, SolverError> {
+        if let Some(result) = self.trivial_result() {
+            return Ok(result);
+        }
+
+        let started = Instant::now();
+        let mut input = NamedTempFile::new().map_err(SolverError::WriteFailed)?;
+        let mut writer = BufWriter::new(input.as_file_mut());
+        self.write_dimacs(&mut writer)
+            .and_then(|_| writer.flush())
+            .map_err(SolverError::WriteFailed)?;
+        drop(writer);
+        let result_file = NamedTempFile::new().map_err(SolverError::WriteFailed)?;
+
+        let options = SolveOptions {
+            input_file: Some(input.path().to_path_buf()),
+            result_file: Some(result_file.path().to_path_buf()),
+            ..options.clone()
+        };
+        let command = build_command_with(solver, &options)?;
+        let solver_name = solver_name(&command);
+        let mut solver = spawn_solver(command)?;
+        // The input is in the file, nothing is sent to stdin.
+        drop(solver.stdin.take());
+        let start_time = Instant::now();
+        let mut output = wait_for_solver(solver).map_err(SolverError::WaitFailed)?;
+        let elapsed_time = start_time.elapsed();
+
+        // The answer is parsed together with anything the solver printed.
+        let answer = fs::read(result_file.path()).map_err(SolverError::WaitFailed)?;
+        output.stdout.extend(answer);
+        self.result_from_output(output, solver_name, elapsed_time, started)
+    }
+
+    /// Solve like [`CnfSat::evaluate_with`] and have the solver write a DRAT proof to `proof_path`
+    /// if the formula is unsatisfiable, using the solver's own proof options, see [`crate::solvers::add_proof`].
+    /// No file is written for oxisat, which cannot write proofs, nor for formulas with an empty clause
+    /// or contradictory units, which are decided without running the solver.
+    pub fn evaluate_with_proof(
+        &self,
+        solver: &Solver,
+        options: &SolveOptions,
+        proof_path: &Path,
+    ) -> EvaluationResult {
+        let options = SolveOptions {
+            proof: Some(proof_path.to_path_buf()),
+            ..options.clone()
+        };
+        self.evaluate_with(solver, &options)
+    }
+
+    /// Solve with the given literals temporarily fixed, without modifying the stored clauses.
+    pub fn evaluate_with_assumptions(
+        &self,
+        solver_command: Command,
+        assumptions: &[Literal],
+    ) -> EvaluationResult {
+        self.run_solver(solver_command, assumptions)
+    }
+
+    /// Like [`CnfSat::evaluate_with_assumptions`], but failures of the solver are returned as errors.
+    pub fn try_evaluate_with_assumptions(
+        &self,
+        solver_command: Command,
+        assumptions: &[Literal],
+    ) -> Result<EvaluationResult, SolverError> {
+        self.try_run_solver(solver_command, assumptions)
+    }
+
+    /// Solve with phase hints, the values the search should try first, e.g. taken from the model
+    /// of a similar formula.
+    ///
+    /// None of the supported solvers take the phases of single variables on their command line,
+    /// so the hints are tried as assumptions first, and if no model is found with all of them,
+    /// the formula is solved again without them. The times of the result cover both solves.
+    pub fn evaluate_with_phases(
+        &self,
+        command_factory: impl Fn() -> Command,
+        phases: &[Literal],
+    ) -> EvaluationResult {
+        self.try_evaluate_with_phases(command_factory, phases)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`CnfSat::evaluate_with_phases`], but failures of the solver are returned as errors.
+    pub fn try_evaluate_with_phases(
+        &self,
+        command_factory: impl Fn() -> Command,
+        phases: &[Literal],
+    ) -> Result<EvaluationResult, SolverError> {
+        let hinted = self.try_run_solver(command_factory(), phases)?;
+        if phases.is_empty() || matches!(hinted, EvaluationResult::Sat { .. }) {
+            return Ok(hinted);
+        }
+
+        let mut result = self.try_run_solver(command_factory(), &[])?;
+        match &mut result {
+            EvaluationResult::Sat { solve_time, total_time, .. }
+            | EvaluationResult::Unsat { solve_time, total_time, .. }
+            | EvaluationResult::Unknown { solve_time, total_time, .. }
+            | EvaluationResult::Timeout { solve_time, total_time, .. } => {
+                *solve_time += hinted.solve_time();
+                *total_time += hinted.total_time();
+            }
+        }
+        Ok(result)
+    }
+
+    /// Solve the formula under each of the assumption sets in order.
+    ///
+    /// None of the supported solvers accept assumptions on their command line,
+    /// so every set is solved by a fresh solver process with the assumptions added as unit clauses.
+    pub fn solve_sequence(
+        &self,
+        command_factory: impl Fn() -> Command,
+        assumption_sets: &[Vec<Literal>],
+    ) -> Vec<EvaluationResult> {
+        assumption_sets
+            .iter()
+            .map(|assumptions| self.evaluate_with_assumptions(command_factory(), assumptions))
+            .collect()
+    }
+
+    /// Solve the formula `runs` times and collect the distribution of solve times.
+    ///
+    /// The command factory receives the index of the run, meant to be used as the solver's seed,
+    /// which makes the sweep reproducible.
+    pub fn benchmark(&self, command_factory: impl Fn(u64) -> Command, runs: usize) -> TimingDistribution {
+        assert!(runs > 0, "At least one run is required.");
+
+        let mut times: Vec<_> = (0..runs as u64)
+            .map(|seed| self.evaluate(command_factory(seed)).solve_time())
+            .collect();
+        times.sort_unstable();
+
+        let seconds: Vec<_> = times.iter().map(|time| time.as_secs_f64()).collect();
+        let mean = seconds.iter().sum::<f64>() / runs as f64;
+        let variance = seconds.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / runs as f64;
+
+        let median = if runs % 2 == 1 {
+            times[runs / 2]
+        } else {
+            (times[runs / 2 - 1] + times[runs / 2]) / 2
+        };
+
+        TimingDistribution {
+            runs,
+            min: times[0],
+            median,
+            max: times[runs - 1],
+            mean: Duration::from_secs_f64(mean),
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        }
+    }
+
+    /// Literals forced by the fixed ones, found by checking for every other variable whether
+    /// the opposite value is satisfiable together with `fixed`. Models found along the way
+    /// rule out further candidates without solving.
+    ///
+    /// Returns no literals if `fixed` cannot be satisfied at all.
+    /// Fails with [`SolverError::Undecided`] if the solver gives up on any of the checks.
+    pub fn implied_literals(
+        &self,
+        command_factory: impl Fn() -> Command,
+        fixed: &[Literal],
+    ) -> Result<Vec<Literal>, SolverError> {
+        let model = match self.try_decide(command_factory(), fixed)? {
+            EvaluationResult::Sat { model, .. } => model,
+            _ => return Ok(Vec::new()),
+        };
+
+        Ok(self.implied_by_model(command_factory, fixed, &model)?.0)
+    }
+
+    /// A minimal subset of the assumptions the formula is unsatisfiable with, or `None` if it is satisfiable
+    /// with all of them or the solver gives up. Empty if the formula is unsatisfiable on its own.
+    ///
+    /// Drops the assumptions one at a time, leaving out each one the formula stays unsatisfiable without.
+    /// Assumptions whose removal the solver gives up on are kept, so with a time limit the core may not be minimal.
+    pub fn failed_assumptions(
+        &self,
+        command_factory: impl Fn() -> Command,
+        assumptions: &[Literal],
+    ) -> Option<Vec<Literal>> {
+        let mut core = match self.evaluate_with_assumptions(command_factory(), assumptions) {
+            EvaluationResult::Unsat { core, .. } => core,
+            _ => return None,
+        };
+
+        let mut index = 0;
+        while index < core.len() {
+            let mut without = core.clone();
+            without.remove(index);
+            match self.evaluate_with_assumptions(command_factory(), &without) {
+                EvaluationResult::Unsat { core: smaller, .. } => core = smaller,
+                _ => index += 1,
+            }
+        }
+
+        Some(core)
+    }
+
+    /// Backbones of the formula, the literals true in every model, or `None` if it is not satisfiable.
+    /// Fails with [`SolverError::Undecided`] if the solver gives up on any of the checks.
+    pub fn find_backbones(
+        &self,
+        command_factory: impl Fn() -> Command,
+    ) -> Result<Option<Vec<Literal>>, SolverError> {
+        match self.try_decide(command_factory(), &[])? {
+            EvaluationResult::Sat { model, .. } => {
+                Ok(Some(self.implied_by_model(command_factory, &[], &model)?.0))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Indices of a minimal correction set, clauses whose removal makes the formula satisfiable
+    /// while removing any proper subset of them does not. Empty for a satisfiable formula.
+    ///
+    /// Grows a maximal satisfiable subset one clause at a time, its complement is the correction set.
+    /// Every model found also adds all the other clauses it satisfies, so the solver is called
+    /// at most once per clause. Fails with [`SolverError::Undecided`] if the solver gives up
+    /// on any of the subsets.
+    pub fn find_mcs(&self, command_factory: impl Fn() -> Command) -> Result<Vec<usize>, SolverError> {
+        let mut satisfied = vec![false; self.clauses.len()];
+        let mut subset = self.clone();
+        subset.soft_clauses.clear();
+
+        for candidate in 0..self.clauses.len() {
+            if satisfied[candidate] || self.clauses[candidate].is_empty() {
+                continue;
+            }
+
+            subset.clauses = (0..self.clauses.len())
+                .filter(|&i| satisfied[i] || i == candidate)
+                .map(|i| self.clauses[i].clone())
+                .collect();
+            if let EvaluationResult::Sat { model, .. } = subset.try_decide(command_factory(), &[])? {
+                for (clause, satisfied) in self.clauses.iter().zip(satisfied.iter_mut()) {
+                    if clause
+                        .iter()
+                        .any(|(id, value)| model.get_result_by_id(id) == Some(value))
+                    {
+                        *satisfied = true;
+                    }
+                }
+            }
+        }
+
+        Ok((0..self.clauses.len()).filter(|&i| !satisfied[i]).collect())
+    }
+
+    /// Every model of the formula, found by solving again with a clause blocking each found model
+    /// until the formula becomes unsatisfiable. Meant for small formulas, the solver is called
+    /// once per model. Fails with [`SolverError::Undecided`] if the solver gives up, as the models
+    /// found until then may not be all of them.
+    pub fn all_models(&self, command_factory: impl Fn() -> Command) -> Result<Vec<SatModel>, SolverError> {
+        let all_variables: Vec<_> = self.all_variables().collect();
+        let mut models = Vec::new();
+        self.enumerate_models(command_factory, &all_variables, |model| models.push(model))?;
+        Ok(models)
+    }
+
+    /// Number of models of the formula, see [`CnfSat::all_models`].
+    pub fn count_models(&self, command_factory: impl Fn() -> Command) -> Result<usize, SolverError> {
+        let all_variables: Vec<_> = self.all_variables().collect();
+        self.count_models_over(command_factory, &all_variables)
+    }
+
+    /// Number of distinct assignments to `variables` that extend to a model. Counting over
+    /// the variables of interest skips models differing only in the others, e.g. auxiliary ones.
+    pub fn count_models_over(
+        &self,
+        command_factory: impl Fn() -> Command,
+        variables: &[VarId],
+    ) -> Result<usize, SolverError> {
+        let mut count = 0;
+        self.enumerate_models(command_factory, variables, |_| count += 1)?;
+        Ok(count)
+    }
+
+    /// Solve and block models over `over` until the formula is unsatisfiable, passing each model
+    /// to `visit`. Blocking clauses are added to a copy, the formula itself stays unchanged.
+    fn enumerate_models(
+        &self,
+        command_factory: impl Fn() -> Command,
+        over: &[VarId],
+        mut visit: impl FnMut(SatModel),
+    ) -> Result<(), SolverError> {
+        let mut blocked = self.clone();
+        while let EvaluationResult::Sat { model, .. } = blocked.try_decide(command_factory(), &[])? {
+            let mut blocking_clause = CnfClause::new();
+            for &id in over {
+                if let Some(value) = model.get_result_by_id(id) {
+                    blocking_clause.set(id, !value);
+                }
+            }
+            blocked.add_clause(blocking_clause);
+            visit(model);
+        }
+        Ok(())
+    }
+
+    /// Satisfiability, a model, backbones, component count and size of the formula in one go.
+    ///
+    /// The first model serves as the reference for the backbone search, so the solver is called
+    /// once plus at most once per variable assigned in that model; every further model rules out
+    /// all candidates it flips. The number of calls made is part of the result.
+    ///
+    /// A formula the solver gives up on has the status UNKNOWN or TIMEOUT and no backbones,
+    /// giving up during the backbone search fails with [`SolverError::Undecided`].
+    pub fn analyze(&self, command_factory: impl Fn() -> Command) -> Result<FormulaAnalysis, SolverError> {
+        let result = self.try_evaluate(command_factory())?;
+        let mut analysis = FormulaAnalysis {
+            status: result.status().to_string(),
+            model: None,
+            backbones: Vec::new(),
+            component_count: self.components().len(),
+            variable_count: self.variable_count(),
+            clause_count: self.clause_count(),
+            solver_calls: 1,
+        };
+
+        if let EvaluationResult::Sat { model, .. } = result {
+            let (backbones, solver_calls) = self.implied_by_model(command_factory, &[], &model)?;
+            analysis.backbones = backbones;
+            analysis.solver_calls += solver_calls;
+            analysis.model = Some(model);
+        }
+
+        Ok(analysis)
+    }
+
+    /// Literals forced by `fixed`, given a model satisfying `fixed`.
+    /// Also returns how many times the solver was called.
+    fn implied_by_model(
+        &self,
+        command_factory: impl Fn() -> Command,
+        fixed: &[Literal],
+        model: &SatModel,
+    ) -> Result<(Vec<Literal>, usize), SolverError> {
+        let mut candidates: Vec<Option<bool>> = self
+            .all_variables()
+            .map(|id| model.get_result_by_id(id))
+            .collect();
+        // Fixed variables beyond the created ones are no candidates anyway.
+        for &(id, _) in fixed {
+            if let Some(candidate) = candidates.get_mut(id.0) {
+                *candidate = None;
+            }
+        }
+
+        let mut implied = Vec::new();
+        let mut solver_calls = 0;
+        let mut assumptions = fixed.to_vec();
+        for id in 0..candidates.len() {
+            let Some(value) = candidates[id] else {
+                continue;
+            };
+
+            assumptions.push((VarId(id), !value));
+            solver_calls += 1;
+            match self.try_decide(command_factory(), &assumptions)? {
+                EvaluationResult::Sat { model, .. } => {
+                    for (other, candidate) in candidates.iter_mut().enumerate() {
+                        if candidate.is_some() && *candidate != model.get_result_by_id(VarId(other)) {
+                            *candidate = None;
+                        }
+                    }
+                }
+                _ => implied.push((VarId(id), value)),
+            }
+            assumptions.pop();
+        }
+
+        Ok((implied, solver_calls))
+    }
+
+    /// Like [`CnfSat::try_evaluate_with_assumptions`], but a solver that gives up or is stopped
+    /// fails with [`SolverError::Undecided`], so the result is either SAT or UNSAT.
+    fn try_decide(
+        &self,
+        solver_command: Command,
+        assumptions: &[Literal],
+    ) -> Result<EvaluationResult, SolverError> {
+        match self.try_evaluate_with_assumptions(solver_command, assumptions)? {
+            EvaluationResult::Unknown { .. } | EvaluationResult::Timeout { .. } => {
+                Err(SolverError::Undecided { assumptions: assumptions.to_vec() })
+            }
+            result => Ok(result),
+        }
+    }
+
+    /// Like [`CnfSat::evaluate`], but the DIMACS is generated by a background thread while the solver
+    /// is already reading it, instead of being built in memory first. This overlaps generating large
+    /// formulas with solving them; the measured time includes the generation.
+    pub fn evaluate_streaming(&self, solver_command: Command) -> EvaluationResult {
+        self.try_evaluate_streaming(solver_command)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`CnfSat::evaluate_streaming`], but failures of the solver are returned as errors.
+    pub fn try_evaluate_streaming(&self, solver_command: Command) -> Result<EvaluationResult, SolverError> {
+        if let Some(result) = self.trivial_result() {
+            return Ok(result);
+        }
+
+        let started = Instant::now();
+        let solver_name = solver_name(&solver_command);
+        let mut solver = spawn_solver(solver_command)?;
+        let stdin = solver.stdin.take().expect("The solver's stdin is always piped");
+
+        let (written, output) = thread::scope(|scope| {
+            let writer = scope.spawn(move || {
+                let mut writer = BufWriter::new(stdin);
+                self.write_dimacs(&mut writer).and_then(|_| writer.flush())
+            });
+            let output = wait_for_solver(solver);
+            (writer.join().unwrap(), output)
+        });
+        // The input is generated while the solver reads it, so the solver's time includes it.
+        let elapsed_time = started.elapsed();
+
+        // A solver failing while reading breaks the pipe, its own error explains more.
+        let output = output.map_err(SolverError::WaitFailed)?;
+        let result = self.result_from_output(output, solver_name, elapsed_time, started)?;
+        written.map_err(SolverError::WriteFailed)?;
+        Ok(result)
+    }
+
+    fn run_solver(&self, solver_command: Command, units: &[Literal]) -> EvaluationResult {
+        self.try_run_solver(solver_command, units)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Run the solver on the formula with the unit clauses added.
+    /// The DIMACS is written to the solver's stdin directly, without building it in memory.
+    fn try_run_solver(
+        &self,
+        solver_command: Command,
+        units: &[Literal],
+    ) -> Result<EvaluationResult, SolverError> {
+        if let Some(result) = self.trivial_result() {
+            return Ok(result);
+        }
+
+        let started = Instant::now();
+        let solver_name = solver_name(&solver_command);
+        let mut solver = spawn_solver(solver_command)?;
+        let stdin = solver.stdin.take().expect("The solver's stdin is always piped");
+        // The solver runs from now on, reading the input while it is written.
+        let start_time = Instant::now();
+        let mut writer = BufWriter::new(stdin);
+        let written = self
+            .write_dimacs_with_units(&mut writer, units)
+            .and_then(|_| writer.flush());
+        // Close the input so the solver sees its end.
+        drop(writer);
+
+        let output = wait_for_solver(solver).map_err(SolverError::WaitFailed)?;
+        let elapsed_time = start_time.elapsed();
+
+        // A solver failing while reading breaks the pipe, its own error explains more.
+        let mut result = self.result_from_output(output, solver_name, elapsed_time, started)?;
+        written.map_err(SolverError::WriteFailed)?;
+        if let EvaluationResult::Unsat { core, .. } = &mut result {
+            *core = units.to_vec();
+        }
+        Ok(result)
+    }
+
+    /// There is no need to run the solver if the answer is known already.
+    fn trivial_result(&self) -> Option<EvaluationResult> {
+        if !self.is_trivially_unsat() {
+            return None;
+        }
+        Some(EvaluationResult::Unsat {
+            dimacs: String::new(),
+            core: Vec::new(),
+            solver: String::new(),
+            solve_time: Duration::ZERO,
+            total_time: Duration::ZERO,
+        })
+    }
+
+    /// Classify the solver's answer:
+    /// - killed by SIGTERM, SIGKILL or SIGXCPU, as sent by `timeout`-like wrappers and CPU limits: `Timeout`,
+    /// - no status line and an unsuccessful exit: [`SolverError::NonZeroExit`] with the solver's stderr,
+    /// - an `s UNKNOWN` or, from glucose, `s INDETERMINATE` line, printed by solvers reaching
+    ///   their own time limit: `Unknown`,
+    /// - otherwise SAT with the model from the `v` lines, or UNSAT.
+    ///
+    /// The total time is measured from `started` until the output is parsed.
+    /// The `solver` name is only kept in SAT and UNSAT results.
+    fn result_from_output(
+        &self,
+        output: Output,
+        solver: String,
+        elapsed_time: Duration,
+        started: Instant,
+    ) -> Result<EvaluationResult, SolverError> {
+        let Output { status, stdout, stderr } = output;
+        let stdout = String::from_utf8(stdout).map_err(SolverError::NonUtf8Output)?;
+        let stdout = minisat_result_to_competition(&stdout).unwrap_or(stdout);
+
+        // Some solver builds print the result to stderr, use it if stdout has no status line.
+        let has_status = |output: &str| output.lines().any(is_status_line);
+        let stderr = String::from_utf8_lossy(&stderr).into_owned();
+        let dimacs_output = if has_status(&stdout) {
+            stdout
+        } else if has_status(&stderr) {
+            stderr
+        } else if killed_on_timeout(status) {
+            stdout
+        } else if !status.success() {
+            // Solvers exit with 10 or 20 when they find an answer, so the exit code alone
+            // does not mean failure, only together with a missing answer.
+            return Err(SolverError::NonZeroExit {
+                code: status.code(),
+                stderr,
+            });
+        } else {
+            stdout
+        };
+
+        if killed_on_timeout(status) {
+            return Ok(EvaluationResult::Timeout {
+                dimacs: dimacs_output,
+                solve_time: elapsed_time,
+                total_time: started.elapsed(),
+            });
+        }
+
+        let gave_up = |line: &str| line.starts_with("s UNKNOWN") || line.starts_with("s INDETERMINATE");
+        if dimacs_output.lines().any(gave_up) {
+            return Ok(EvaluationResult::Unknown {
+                dimacs: dimacs_output,
+                solve_time: elapsed_time,
+                total_time: started.elapsed(),
+            });
+        }
+
+        let model = self.result_from_dimacs(&dimacs_output);
+        let total_time = started.elapsed();
+        match model {
+            Ok(model) => Ok(EvaluationResult::Sat {
+                dimacs: dimacs_output,
+                model,
+                solver,
+                solve_time: elapsed_time,
+                total_time,
+            }),
+            Err(ModelError::Unsatisfiable) => Ok(EvaluationResult::Unsat {
+                dimacs: dimacs_output,
+                core: Vec::new(),
+                solver,
+                solve_time: elapsed_time,
+                total_time,
+            }),
+            Err(ModelError::MissingModel) => Err(SolverError::MissingModel),
+            Err(ModelError::Undecided) => Ok(EvaluationResult::Unknown {
+                dimacs: dimacs_output,
+                solve_time: elapsed_time,
+                total_time,
+            }),
+        }
+    }
+}
+
+/// File name of the command's program. For a command run through a wrapper, this is the wrapper.
+fn solver_name(command: &Command) -> String {
+    Path::new(command.get_program())
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The answers of a SAT solver in the competition format.
+const STATUS_LINES: [&str; 4] = ["s SATISFIABLE", "s UNSATISFIABLE", "s UNKNOWN", "s INDETERMINATE"];
+
+fn is_status_line(line: &str) -> bool {
+    STATUS_LINES.contains(&line.trim_end())
+}
+
+/// Pass the solver's stderr through to ours while it runs, so that its progress stays visible,
+/// and keep a copy for finding the answer there and for error messages.
+fn tee_stderr(mut stderr: ChildStderr) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut captured = Vec::new();
+        let mut buffer = [0; 8192];
+        loop {
+            match stderr.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => {
+                    let _ = io::stderr().write_all(&buffer[..read]);
+                    captured.extend_from_slice(&buffer[..read]);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+        captured
+    })
+}
+
+/// Like [`Child::wait_with_output`], with the solver's stderr passed through, see [`tee_stderr`].
+fn wait_for_solver(mut solver: Child) -> io::Result<Output> {
+    let stderr = solver.stderr.take().map(tee_stderr);
+    let mut output = solver.wait_with_output()?;
+    if let Some(stderr) = stderr {
+        output.stderr = stderr.join().unwrap();
+    }
+    Ok(output)
+}
+
+fn spawn_solver(mut solver_command: Command) -> Result<Child, SolverError> {
+    solver_command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|source| {
+            let path = PathBuf::from(solver_command.get_program());
+            // A missing execute bit, typically lost when the solvers were checked out or copied.
+            if source.kind() == io::ErrorKind::PermissionDenied {
+                SolverError::NotExecutable { path }
+            } else {
+                SolverError::SpawnFailed { path, source }
+            }
+        })
+}
+
+/// Minisat writes its answer to the result file as a `SAT` line followed by the model,
+/// or as `UNSAT` or `INDET`, instead of the `s` and `v` lines of the competition format.
+/// Returns `None` if the output already has a status line or no minisat answer.
+fn minisat_result_to_competition(output: &str) -> Option<String> {
+    if output.lines().any(is_status_line) {
+        return None;
+    }
+
+    let mut lines = output.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        match line {
+            "SAT" => {
+                let model = lines.next().unwrap_or_default();
+                return Some(format!("s SATISFIABLE\nv {model}\n"));
+            }
+            "UNSAT" => return Some("s UNSATISFIABLE\n".to_string()),
+            "INDET" => return Some("s UNKNOWN\n".to_string()),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(unix)]
+fn killed_on_timeout(status: ExitStatus) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+
+    const SIGKILL: i32 = 9;
+    const SIGTERM: i32 = 15;
+    const SIGXCPU: i32 = 24;
+    matches!(status.signal(), Some(SIGKILL | SIGTERM | SIGXCPU))
+}
+
+#[cfg(not(unix))]
+fn killed_on_timeout(_status: ExitStatus) -> bool {
+    false
+}
+
+/// Space-separated 1-based DIMACS literals, without the terminating 0.
+fn dimacs_literals(literals: impl IntoIterator<Item = Literal>) -> String {
+    literals
+        .into_iter()
+        .map(|(id, value)| {
+            if value {
+                format!("{}", id.dimacs())
+            } else {
+                format!("-{}", id.dimacs())
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Whether every literal of `subset` is contained in `superset`; both have to be sorted.
+fn is_sorted_subset(subset: &[Literal], superset: &[Literal]) -> bool {
+    let mut superset = superset.iter();
+    subset
+        .iter()
+        .all(|literal| superset.by_ref().any(|other| other == literal))
+}
+
+impl Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::NotExecutable { path } => write!(
+                f,
+                "Solver {} is not executable, try `chmod +x {}`",
+                path.display(),
+                path.display()
+            ),
+            SolverError::SpawnFailed { path, source } => {
+                write!(f, "Failed to run solver {}: {source}", path.display())
+            }
+            SolverError::WriteFailed(err) => write!(f, "Failed to write the solver's input: {err}"),
+            SolverError::WaitFailed(err) => write!(f, "Failed to get the solver's output: {err}"),
+            SolverError::NonUtf8Output(err) => write!(f, "Non-UTF8 output from the solver: {err}"),
+            SolverError::NonZeroExit { code, stderr } => {
+                match code {
+                    Some(code) => write!(f, "Solver failed with exit code {code}")?,
+                    None => write!(f, "Solver was killed by a signal")?,
+                }
+                if !stderr.trim().is_empty() {
+                    write!(f, ":\n{}", stderr.trim_end())?;
+                }
+                Ok(())
+            }
+            SolverError::MissingModel => write!(
+                f,
+                "Solver found a model but did not print it, it may need an option to print the values"
+            ),
+            SolverError::NoSolverFound { solver_dir } => write!(
+                f,
+                "No solver found in {}, set --solver-dir or ${SOLVER_DIR_ENV} to the directory with the solvers",
+                solver_dir.display()
+            ),
+            SolverError::Undecided { assumptions } => {
+                write!(f, "Solver gave no answer")?;
+                if !assumptions.is_empty() {
+                    let literals: Vec<_> = assumptions
+                        .iter()
+                        .map(|&(id, value)| format!("{}{}", if value { "" } else { "-" }, id.dimacs()))
+                        .collect();
+                    write!(f, " under the assumptions {}", literals.join(" "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(err) => write!(f, "Failed to read dimacs: {err}"),
+            ParseError::Syntax(description) => write!(f, "Failed to parse dimacs: {description}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(err) => Some(err),
+            ParseError::Syntax(_) => None,
+        }
+    }
+}
+
+impl Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Unsatisfiable => write!(f, "The formula is unsatisfiable"),
+            ModelError::MissingModel => write!(f, "The solver found a model but did not print it"),
+            ModelError::Undecided => write!(f, "The solver did not decide the formula"),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl std::error::Error for SolverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SolverError::NotExecutable { .. } => None,
+            SolverError::SpawnFailed { source, .. }
+            | SolverError::WriteFailed(source)
+            | SolverError::WaitFailed(source) => Some(source),
+            SolverError::NonUtf8Output(err) => Some(err),
+            SolverError::NonZeroExit { .. }
+            | SolverError::MissingModel
+            | SolverError::NoSolverFound { .. }
+            | SolverError::Undecided { .. } => None,
+        }
+    }
+}
+
+impl EvaluationResult {
+    pub fn status(&self) -> &'static str {
+        match self {
+            EvaluationResult::Sat { .. } => "SAT",
+            EvaluationResult::Unsat { .. } => "UNSAT",
+            EvaluationResult::Unknown { .. } => "UNKNOWN",
+            EvaluationResult::Timeout { .. } => "TIMEOUT",
+        }
+    }
+
+    pub fn solve_time(&self) -> Duration {
+        match self {
+            EvaluationResult::Sat { solve_time, .. }
+            | EvaluationResult::Unsat { solve_time, .. }
+            | EvaluationResult::Unknown { solve_time, .. }
+            | EvaluationResult::Timeout { solve_time, .. } => *solve_time,
+        }
+    }
+
+    /// Name of the solver's binary, `None` for results without it.
+    pub fn solver(&self) -> Option<&str> {
+        match self {
+            EvaluationResult::Sat { solver, .. } | EvaluationResult::Unsat { solver, .. } => Some(solver),
+            EvaluationResult::Unknown { .. } | EvaluationResult::Timeout { .. } => None,
+        }
+    }
+
+    pub fn total_time(&self) -> Duration {
+        match self {
+            EvaluationResult::Sat { total_time, .. }
+            | EvaluationResult::Unsat { total_time, .. }
+            | EvaluationResult::Unknown { total_time, .. }
+            | EvaluationResult::Timeout { total_time, .. } => *total_time,
+        }
+    }
+}
+
+impl Display for CnfStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} vars, {} clauses, {} literals, {} unit clauses, clause length mean {:.2}, median {}, max {}",
+            self.variable_count,
+            self.clause_count,
+            self.literal_count,
+            self.unit_clause_count,
+            self.mean_clause_length,
+            self.median_clause_length,
+            self.max_clause_length
+        )
+    }
+}
+
+impl Display for TimingDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} runs: min {:?}, median {:?}, max {:?}, mean {:?}, stddev {:?}",
+            self.runs, self.min, self.median, self.max, self.mean, self.stddev
+        )
+    }
+}
+
+impl SatModel {
+    /// Variables referenced by clauses without being created are named `#<id>`, as in
+    /// [`CnfSat::to_readable`].
+    pub fn from_vec(sat: &CnfSat, model: &Vec<Literal>) -> SatModel {
+        let mut results_by_name = HashMap::new();
+        let mut results_by_id = BTreeMap::new();
+        let mut names_by_id = HashMap::new();
+
+        for (id, value) in model {
+            let name = match sat.get_variable_by_id(*id) {
+                Some(variable) => variable.name.to_string(),
+                None => format!("#{}", id.0),
+            };
+            results_by_id.insert(*id, *value);
+            results_by_name.insert(name.clone(), *value);
+            names_by_id.insert(*id, name);
+        }
+        SatModel {
+            results_by_name,
+            results_by_id,
+            names_by_id,
+        }
+    }
+
+    pub fn get_result_by_id(&self, id: VarId) -> Option<bool> {
         let value = self.results_by_id.get(&id)?;
         Some(*value)
     }
 
-    pub fn get_result_by_name(&self, name: &str) -> Option<bool> {
-        let value = self.results_by_name.get(name)?;
-        Some(*value)
+    pub fn get_result_by_name(&self, name: &str) -> Option<bool> {
+        let value = self.results_by_name.get(name)?;
+        Some(*value)
+    }
+
+    /// The value of the variable, or `default` if the model leaves it unassigned.
+    /// Some solvers omit variables that do not matter, those are free to take either value.
+    pub fn get_or_default(&self, name: &str, default: bool) -> bool {
+        self.get_result_by_name(name).unwrap_or(default)
+    }
+
+    /// Name and value of every assigned variable, ordered by id.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, bool)> + '_ {
+        self.results_by_id
+            .iter()
+            .map(|(id, &value)| (self.names_by_id[id].as_str(), value))
+    }
+
+    /// Id and value of every assigned variable, ordered by id.
+    pub fn iter_ids(&self) -> impl Iterator<Item = Literal> + '_ {
+        self.results_by_id.iter().map(|(&id, &value)| (id, value))
+    }
+
+    /// Names of the variables assigned true, ordered by id.
+    pub fn true_variables(&self) -> Vec<&str> {
+        self.iter().filter(|&(_, value)| value).map(|(name, _)| name).collect()
+    }
+
+    /// Ids of the variables assigned true, in ascending order.
+    pub fn true_ids(&self) -> Vec<VarId> {
+        self.iter_ids().filter(|&(_, value)| value).map(|(id, _)| id).collect()
+    }
+
+    /// A complete assignment of variables `0..variable_count`, filling in unassigned variables by the policy.
+    pub fn to_literal_vec(&self, variable_count: usize, policy: &ModelFillPolicy) -> Vec<Literal> {
+        (0..variable_count)
+            .map(VarId)
+            .map(|id| {
+                let value = self.get_result_by_id(id).unwrap_or_else(|| match policy {
+                    ModelFillPolicy::AllFalse => false,
+                    ModelFillPolicy::AllTrue => true,
+                    ModelFillPolicy::FromMap(values) => values.get(&id).copied().unwrap_or(false),
+                });
+                (id, value)
+            })
+            .collect()
+    }
+
+    /// The model in the SAT competition output format, a status line and a complete value line.
+    pub fn to_competition_output(&self, variable_count: usize, policy: &ModelFillPolicy) -> String {
+        let literals = self.to_literal_vec(variable_count, policy);
+        if literals.is_empty() {
+            "s SATISFIABLE\nv 0\n".to_string()
+        } else {
+            format!("s SATISFIABLE\nv {} 0\n", dimacs_literals(literals))
+        }
+    }
+
+    /// Literals fixing the given variables to their values in this model.
+    /// Variables the model does not assign are skipped.
+    pub fn as_assumptions(&self, over: &[VarId]) -> Vec<Literal> {
+        over.iter()
+            .filter_map(|&id| Some((id, self.get_result_by_id(id)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A formula with a variable for each name, and their ids.
+    fn named_sat(names: &[&str]) -> (CnfSat, Vec<VarId>) {
+        let mut sat = CnfSat::new();
+        let ids = names.iter().map(|name| sat.create_variable(name)).collect();
+        (sat, ids)
+    }
+
+    /// Whether the clauses have a model with the given literals, by a backtracking search
+    /// that is only fast enough for the small formulas in tests.
+    pub(crate) fn satisfiable_with(sat: &CnfSat, assumptions: &[Literal]) -> bool {
+        let referenced = sat.max_referenced_variable().map_or(0, |id| id.0 + 1);
+        let mut values = vec![None; referenced.max(sat.variable_count())];
+        for &(id, value) in assumptions {
+            if values[id.0].is_some_and(|assigned| assigned != value) {
+                return false;
+            }
+            values[id.0] = Some(value);
+        }
+        extend_assignment(&sat.clauses, &mut values)
+    }
+
+    fn extend_assignment(clauses: &[CnfClause], values: &mut [Option<bool>]) -> bool {
+        let Some(clause) = clauses
+            .iter()
+            .find(|clause| !clause.iter().any(|(id, value)| values[id.0] == Some(value)))
+        else {
+            return true;
+        };
+        // Either the first open literal is true, or it is false and one of the others is.
+        let mut assigned = Vec::new();
+        for (id, value) in clause.iter() {
+            if values[id.0].is_none() {
+                values[id.0] = Some(value);
+                if extend_assignment(clauses, values) {
+                    return true;
+                }
+                values[id.0] = Some(!value);
+                assigned.push(id);
+            }
+        }
+        for id in assigned {
+            values[id.0] = None;
+        }
+        false
+    }
+
+    /// Checks that for every assignment of the variables, the formula can be satisfied exactly
+    /// when `constraint` holds for it.
+    pub(crate) fn assert_holds_exactly_when(
+        sat: &CnfSat,
+        variables: &[VarId],
+        constraint: impl Fn(&[bool]) -> bool,
+    ) {
+        for assignment in 0u32..1 << variables.len() {
+            let values: Vec<_> = (0..variables.len()).map(|i| assignment & (1 << i) != 0).collect();
+            let literals: Vec<_> = variables.iter().copied().zip(values.iter().copied()).collect();
+            assert_eq!(satisfiable_with(sat, &literals), constraint(&values), "assignment {values:?}");
+        }
+    }
+
+    /// Output of a solver process exiting with the given code.
+    #[cfg(unix)]
+    fn solver_output(code: i32, stdout: &str, stderr: &str) -> Output {
+        use std::os::unix::process::ExitStatusExt;
+
+        Output {
+            status: ExitStatus::from_raw(code << 8),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    /// A solver that reads the formula and prints `stdout`, escapes included.
+    #[cfg(unix)]
+    fn fake_solver(stdout: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(format!("cat > /dev/null; printf '{stdout}'"));
+        command
+    }
+
+    /// A solver trying every assignment in order, so it finds the first model with the lowest
+    /// variables unset. Only fast enough for formulas with a few variables.
+    #[cfg(unix)]
+    pub(crate) fn brute_force_solver() -> Command {
+        let mut command = Command::new("awk");
+        command.arg(
+            r#"
+            BEGIN { clauses = 0 }
+            /^p/ { n = $3; next }
+            /^c/ { next }
+            {
+                for (i = 1; i <= NF; i++) {
+                    if ($i == 0) clauses++; else literals[clauses, length_of[clauses]++] = $i
+                }
+            }
+            function value(assignment, v) { return int(assignment / 2 ^ (v - 1)) % 2 }
+            END {
+                for (assignment = 0; assignment < 2 ^ n; assignment++) {
+                    satisfied = 1
+                    for (c = 0; c < clauses && satisfied; c++) {
+                        satisfied = 0
+                        for (k = 0; k < length_of[c]; k++) {
+                            l = literals[c, k]
+                            if ((l > 0) == value(assignment, l > 0 ? l : -l)) { satisfied = 1; break }
+                        }
+                    }
+                    if (satisfied) {
+                        printf "s SATISFIABLE\nv"
+                        for (v = 1; v <= n; v++) printf " %d", value(assignment, v) ? v : -v
+                        print " 0"
+                        exit 10
+                    }
+                }
+                print "s UNSATISFIABLE"
+                exit 20
+            }
+            "#,
+        );
+        command
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn model_on_stderr() {
+        let (sat, v) = named_sat(&["a", "b"]);
+        let output = solver_output(10, "solving...\nsearch done\n", "c progress\ns SATISFIABLE\nv 1 -2 0\n");
+        let result = sat.result_from_output(output, String::new(), Duration::ZERO, Instant::now());
+        let Ok(EvaluationResult::Sat { model, .. }) = result else {
+            panic!("Expected a model");
+        };
+        assert_eq!(model.get_result_by_id(v[0]), Some(true));
+        assert_eq!(model.get_result_by_id(v[1]), Some(false));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn solver_limit_is_unknown() {
+        let (sat, v) = named_sat(&["a"]);
+        let classify = |stdout: &str| {
+            let output = solver_output(0, stdout, "");
+            sat.result_from_output(output, String::new(), Duration::ZERO, Instant::now())
+                .unwrap()
+                .status()
+        };
+        assert_eq!(classify("c glucose\ns INDETERMINATE\n"), "UNKNOWN");
+        assert_eq!(classify("s UNKNOWN\n"), "UNKNOWN");
+        assert_eq!(classify("s UNSATISFIABLE\n"), "UNSAT");
+        assert_eq!(classify(&format!("s SATISFIABLE\nv {} 0\n", v[0].dimacs())), "SAT");
+    }
+
+    #[test]
+    fn only_satisfiable_status_has_model() {
+        let (sat, v) = named_sat(&["a", "b"]);
+        let model = sat.result_from_dimacs("c banner\ns SATISFIABLE\nv -1 2 0\n").unwrap();
+        assert_eq!(model.get_result_by_id(v[0]), Some(false));
+        assert_eq!(model.get_result_by_id(v[1]), Some(true));
+
+        assert_eq!(sat.result_from_dimacs("s INDETERMINATE\n").err(), Some(ModelError::Undecided));
+        assert_eq!(sat.result_from_dimacs("s UNKNOWN\n").err(), Some(ModelError::Undecided));
+        assert_eq!(sat.result_from_dimacs("c no answer\n").err(), Some(ModelError::Undecided));
+        assert_eq!(sat.result_from_dimacs("s UNSATISFIABLE\n").err(), Some(ModelError::Unsatisfiable));
+        assert_eq!(sat.result_from_dimacs("s SATISFIABLE\n").err(), Some(ModelError::MissingModel));
+    }
+
+    #[test]
+    fn literal_orders() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[2], true), (v[0], false), (v[1], true)]));
+
+        sat.set_literal_order(ClauseLiteralOrder::InsertionOrder);
+        assert_eq!(sat.to_dimacs(), "p cnf 3 1\n3 -1 2 0\n");
+        sat.set_literal_order(ClauseLiteralOrder::AscendingVar);
+        assert_eq!(sat.to_dimacs(), "p cnf 3 1\n-1 2 3 0\n");
+        sat.set_literal_order(ClauseLiteralOrder::NegativeFirst);
+        assert_eq!(sat.to_dimacs(), "p cnf 3 1\n-1 2 3 0\n");
+
+        let mut clause = CnfClause::from_literals(&[(v[2], false), (v[1], true), (v[0], true)]);
+        clause.set(v[1], true);
+        assert_eq!(clause.len(), 3);
+        assert_eq!(clause.get(v[2]), Some(false));
+        assert_eq!(clause.get(v[1]), Some(true));
+        sat.add_clause(clause);
+        assert_eq!(sat.to_dimacs(), "p cnf 3 2\n-1 2 3 0\n-3 1 2 0\n");
+    }
+
+    #[test]
+    fn tautology_detected() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        let mut clause = CnfClause::new();
+        clause.set(v[0], true);
+        clause.set(v[1], false);
+        assert!(!clause.is_tautology());
+        clause.set(v[0], false);
+        assert!(clause.is_tautology());
+        assert_eq!(clause.len(), 3);
+        assert_eq!(clause.get(v[0]), Some(true));
+
+        sat.add_clause(clause);
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true)]));
+        assert_eq!(sat.to_dimacs(), "p cnf 2 2\n-1 1 -2 0\n2 0\n");
+    }
+
+    #[test]
+    fn dimacs_file_round_trip() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[2], true), (v[2], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true)]));
+
+        let path = std::env::temp_dir().join(format!("round-trip-{}.cnf", std::process::id()));
+        sat.write_dimacs_to_path(&path).unwrap();
+        let read = CnfSat::from_dimacs_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.clause_count(), sat.clause_count());
+        assert_eq!(read.variable_count(), sat.variable_count());
+        assert_eq!(read.to_dimacs(), sat.to_dimacs());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn backbone_search_fails_when_solver_gives_up() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+
+        let unknown = || fake_solver("s UNKNOWN\\n");
+        assert!(matches!(sat.find_backbones(unknown), Err(SolverError::Undecided { .. })));
+        assert!(matches!(sat.analyze(unknown), Ok(FormulaAnalysis { ref status, .. }) if status == "UNKNOWN"));
+
+        // The first solve succeeds, the backbone checks give up.
+        let calls = std::cell::Cell::new(0);
+        let gives_up_later = || {
+            calls.set(calls.get() + 1);
+            match calls.get() {
+                1 => fake_solver("s SATISFIABLE\\nv 1 -2 0\\n"),
+                _ => fake_solver("s UNKNOWN\\n"),
+            }
+        };
+        let Err(SolverError::Undecided { assumptions }) = sat.find_backbones(gives_up_later) else {
+            panic!("Expected the backbone search to be undecided");
+        };
+        assert_eq!(assumptions, vec![(v[0], false)]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn backbone_search_handles_unknown_variables() {
+        let (mut sat, v) = named_sat(&["a"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        let solver = || fake_solver("s SATISFIABLE\\nv 1 0\\n");
+
+        // A fixed literal of a variable that was never created is no candidate.
+        assert_eq!(sat.implied_literals(solver, &[(VarId(10), true)]).unwrap(), vec![]);
+
+        // Flipping a variable the model does not assign finds any model.
+        let empty = SatModel::from_vec(&sat, &vec![]);
+        let neighbor = sat.neighbor_model(solver, &empty, v[0]).unwrap();
+        assert_eq!(neighbor.unwrap().get_result_by_id(v[0]), Some(true));
+    }
+
+    #[test]
+    fn components_with_unknown_variables() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (VarId(7), false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true), (v[2], true)]));
+
+        let components = sat.components();
+        assert_eq!(components.len(), 2);
+        let (first, ids) = &components[0];
+        assert_eq!(ids, &vec![v[0], VarId(7)]);
+        assert_eq!(first.to_readable(), "(a ∨ ¬#7)\n");
+        assert_eq!(components[1].1, vec![v[1], v[2]]);
+    }
+
+    #[test]
+    fn rotatable_with_unknown_variables() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(VarId(4), true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false), (VarId(4), true)]));
+
+        let model = SatModel::from_vec(&sat, &vec![(v[0], true), (v[1], true)]);
+        assert_eq!(sat.rotatable_variables(&model), vec![v[0], v[1]]);
+        let model = SatModel::from_vec(&sat, &vec![(v[0], true), (v[1], false)]);
+        assert_eq!(sat.rotatable_variables(&model), vec![]);
+    }
+
+    #[test]
+    fn subsumption_respects_groups() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        let group = sat.add_group();
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[2], true)]));
+        sat.close_group(group);
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true), (v[2], false)]));
+
+        // The grouped clause with a is subsumed by a clause in no group, the clause outside
+        // the group is not subsumed by the grouped b.
+        assert_eq!(sat.eliminate_subsumed(), 1);
+        assert_eq!(sat.to_dimacs(), "p cnf 3 3\n1 0\n2 0\n2 -3 0\n");
+        sat.remove_group(group);
+        assert_eq!(sat.to_dimacs(), "p cnf 3 2\n1 0\n2 -3 0\n");
+    }
+
+    #[test]
+    fn readable_clauses() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true), (VarId(5), true)]));
+        assert_eq!(sat.to_readable(), "(a ∨ ¬b)\n(b ∨ #5)\n");
+    }
+
+    #[test]
+    fn long_clause_builds_quickly() {
+        let mut sat = CnfSat::new();
+        let variables = sat.create_variables((0..20_000).map(|i| i.to_string()));
+        sat.ensure_at_least_one_set(&variables);
+        assert_eq!(sat.stats().max_clause_length, 20_000);
+    }
+
+    #[test]
+    fn subsumed_clauses_eliminated() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], false), (v[2], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false), (v[0], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true), (v[2], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], false), (v[1], false), (v[2], false)]));
+
+        assert_eq!(sat.eliminate_subsumed(), 1);
+        assert_eq!(sat.to_dimacs(), "p cnf 3 3\n1 -2 0\n2 3 0\n-1 -2 -3 0\n");
+
+        // The empty clause subsumes everything.
+        sat.add_clause(CnfClause::new());
+        assert_eq!(sat.eliminate_subsumed(), 3);
+        assert_eq!(sat.to_dimacs(), "p cnf 3 1\n 0\n");
+    }
+
+    #[test]
+    fn diff_between_formulas() {
+        let (mut before, v) = named_sat(&["a", "b"]);
+        before.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        before.add_clause(CnfClause::from_literals(&[(v[1], false)]));
+
+        let mut after = before.clone();
+        assert!(before.diff(&after).is_empty());
+
+        after.pop_clause();
+        let c = after.create_variable("c");
+        after.add_clause(CnfClause::from_literals(&[(c, true), (v[0], false)]));
+        after.add_clause(CnfClause::from_literals(&[(v[1], true), (v[0], true)]));
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added_clauses, vec![vec![(v[0], false), (c, true)], vec![(v[0], true), (v[1], true)]]);
+        assert_eq!(diff.removed_clauses, vec![vec![(v[1], false)]]);
+        assert_eq!(diff.added_variables, vec!["c".to_string()]);
+    }
+
+    #[test]
+    fn dimacs_output_is_deterministic() {
+        let build = || {
+            let (mut sat, v) = named_sat(&["a", "b", "c", "d"]);
+            sat.add_clause(CnfClause::from_literals(&[(v[3], true), (v[0], true), (v[2], false)]));
+            sat.add_clause(CnfClause::from_literals(&[(v[1], false), (v[3], false)]));
+            sat
+        };
+        let dimacs = build().to_dimacs();
+        assert_eq!(dimacs, "p cnf 4 2\n1 -3 4 0\n-2 -4 0\n");
+        assert!((0..20).all(|_| build().to_dimacs() == dimacs));
+    }
+
+    #[test]
+    fn pop_clause_removes_last() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        assert!(sat.pop_clause().is_none());
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false)]));
+
+        let popped = sat.pop_clause().unwrap();
+        assert_eq!(popped.literals(), vec![(v[1], false)]);
+        assert_eq!(sat.clause_count(), 1);
+        assert_eq!(sat.to_dimacs(), "p cnf 2 1\n1 0\n");
+    }
+
+    #[test]
+    fn values_span_lines() {
+        let (sat, v) = named_sat(&["a", "b", "c", "d"]);
+        let output = "s SATISFIABLE\nv 1\t-2\nv  3\nv -4 0\nv 1 2 3 4 0\n";
+        let model = sat.result_from_dimacs(output).unwrap();
+        let expected = vec![(v[0], true), (v[1], false), (v[2], true), (v[3], false)];
+        assert_eq!(model.iter_ids().collect::<Vec<_>>(), expected);
+
+        // A variable left out by the solver stays unassigned.
+        let model = sat.result_from_dimacs("s SATISFIABLE\nv -1\t4 0\n").unwrap();
+        assert_eq!(model.get_result_by_id(v[0]), Some(false));
+        assert_eq!(model.get_result_by_id(v[1]), None);
+        assert_eq!(model.get_result_by_id(v[3]), Some(true));
+    }
+
+    #[test]
+    fn restore_checkpoint() {
+        let (mut sat, v) = named_sat(&["a"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        let checkpoint = sat.checkpoint();
+        let before = sat.to_wcnf(WcnfVersion::default());
+
+        let b = sat.create_variable("b");
+        sat.add_clause(CnfClause::from_literals(&[(v[0], false), (b, true)]));
+        sat.add_soft_clause(CnfClause::from_literals(&[(b, false)]), 3);
+        let inner = sat.checkpoint();
+        sat.add_clause(CnfClause::from_literals(&[(b, false)]));
+        sat.restore(inner);
+        assert_eq!(sat.clause_count(), 2);
+
+        sat.restore(checkpoint);
+        assert_eq!(sat.to_wcnf(WcnfVersion::default()), before);
+        assert_eq!(sat.try_get_variable("b"), None);
+        // The name is free again.
+        assert_eq!(sat.create_variable("b"), b);
+    }
+
+    #[test]
+    fn maxsat_output() {
+        let (sat, v) = named_sat(&["a", "b", "c"]);
+
+        let output = "c maxsat\no 5\no 2\ns OPTIMUM FOUND\nv 1 -2\nv 3 0\n";
+        let OptimizationResult::Optimum { cost, model } = sat.optimization_result_from_dimacs(output) else {
+            panic!("Expected an optimum");
+        };
+        assert_eq!(cost, 2);
+        assert_eq!(model.true_ids(), vec![v[0], v[2]]);
+
+        // The newer format prints the model as a string of bits.
+        let output = "o 1\ns OPTIMUM FOUND\nv 011\n";
+        let OptimizationResult::Optimum { cost, model } = sat.optimization_result_from_dimacs(output) else {
+            panic!("Expected an optimum");
+        };
+        assert_eq!(cost, 1);
+        assert_eq!(model.true_ids(), vec![v[1], v[2]]);
+
+        assert!(matches!(
+            sat.optimization_result_from_dimacs("s UNSATISFIABLE\n"),
+            OptimizationResult::Unsat
+        ));
+        assert!(matches!(
+            sat.optimization_result_from_dimacs("o 4\ns SATISFIABLE\nv 1 2 3 0\n"),
+            OptimizationResult::Unknown
+        ));
+    }
+
+    #[test]
+    fn verify_model_finds_violated_clause() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false), (v[2], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[2], true), (v[2], false)]));
+
+        let model = SatModel::from_vec(&sat, &vec![(v[0], false), (v[1], true), (v[2], true)]);
+        assert_eq!(sat.verify_model(&model), Ok(()));
+        let model = SatModel::from_vec(&sat, &vec![(v[0], false), (v[1], true), (v[2], false)]);
+        assert_eq!(sat.verify_model(&model), Err(1));
+        // Unassigned variables satisfy nothing, except in the tautology.
+        let model = SatModel::from_vec(&sat, &vec![(v[1], true)]);
+        assert_eq!(sat.verify_model(&model), Err(1));
+        let model = SatModel::from_vec(&sat, &vec![(v[0], true), (v[1], false)]);
+        assert_eq!(sat.verify_model(&model), Ok(()));
+    }
+
+    #[test]
+    fn model_iterates_by_id() {
+        let (sat, v) = named_sat(&["x", "a", "m"]);
+        let model = SatModel::from_vec(&sat, &vec![(v[2], true), (v[0], false), (v[1], true)]);
+        assert_eq!(model.iter().collect::<Vec<_>>(), vec![("x", false), ("a", true), ("m", true)]);
+        assert_eq!(
+            model.iter_ids().collect::<Vec<_>>(),
+            vec![(v[0], false), (v[1], true), (v[2], true)]
+        );
+
+        let partial = SatModel::from_vec(&sat, &vec![(v[1], false)]);
+        assert_eq!(partial.iter().collect::<Vec<_>>(), vec![("a", false)]);
+    }
+
+    #[test]
+    fn unassigned_variables_in_model() {
+        let (sat, v) = named_sat(&["a", "b"]);
+        let model = SatModel::from_vec(&sat, &vec![(v[0], false)]);
+        assert_eq!(model.get_result_by_name("a"), Some(false));
+        assert_eq!(model.get_result_by_name("b"), None);
+        assert_eq!(model.get_result_by_id(v[1]), None);
+        assert!(!model.get_or_default("a", true));
+        assert!(model.get_or_default("b", true));
+        assert!(!model.get_or_default("b", false));
+        assert!(model.get_or_default("missing", true));
+    }
+
+    #[test]
+    fn true_variables_of_model() {
+        let (sat, v) = named_sat(&["a", "b", "c", "d"]);
+        let model = SatModel::from_vec(&sat, &vec![(v[3], true), (v[1], false), (v[0], true)]);
+        assert_eq!(model.true_ids(), vec![v[0], v[3]]);
+        assert_eq!(model.true_variables(), vec!["a", "d"]);
+
+        let none = SatModel::from_vec(&sat, &vec![(v[0], false)]);
+        assert!(none.true_ids().is_empty());
+        assert!(none.true_variables().is_empty());
+    }
+
+    #[test]
+    fn clause_from_literals() {
+        let (_, v) = named_sat(&["a", "b"]);
+        let clause = CnfClause::from_literals(&[(v[1], false), (v[0], true), (v[1], false)]);
+        assert_eq!(clause.len(), 2);
+        assert_eq!(clause.iter().collect::<Vec<_>>(), vec![(v[1], false), (v[0], true)]);
+        assert_eq!(clause.literals(), vec![(v[0], true), (v[1], false)]);
+        assert_eq!(clause.get(v[0]), Some(true));
+        assert_eq!(clause.get(v[1]), Some(false));
+        assert!(!clause.is_tautology());
+
+        assert!(CnfClause::from_literals(&[]).is_empty());
+        assert!(CnfClause::from_literals(&[(v[0], true), (v[0], false)]).is_tautology());
+    }
+
+    #[test]
+    fn variable_ids_and_dimacs_numbers() {
+        assert_eq!(VarId::from_dimacs(1), VarId(0));
+        assert_eq!(VarId(41).dimacs(), 42);
+        assert_eq!(VarId::from_dimacs(VarId(7).dimacs()), VarId(7));
+
+        let (sat, v) = named_sat(&["a", "b"]);
+        assert_eq!(v, vec![VarId(0), VarId(1)]);
+        assert_eq!(sat.get_variable("b"), v[1]);
+        assert_eq!(sat.try_get_variable("c"), None);
+        let variable = sat.get_variable_by_id(v[1]).unwrap();
+        assert_eq!((variable.name(), variable.id()), ("b", v[1]));
+        assert!(sat.get_variable_by_id(VarId(2)).is_none());
+    }
+
+    #[test]
+    fn create_many_variables() {
+        let (mut sat, _) = named_sat(&["first"]);
+        let ids = sat.create_variables((0..3).map(|i| format!("x{i}")));
+        assert_eq!(ids, vec![VarId(1), VarId(2), VarId(3)]);
+        assert_eq!(sat.get_variable("x2"), VarId(3));
+        assert_eq!(sat.variable_count(), 4);
+        assert!(sat.create_variables(Vec::new()).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "unique")]
+    fn create_variables_rejects_duplicates() {
+        let mut sat = CnfSat::new();
+        sat.create_variables(["a".to_string(), "a".to_string()]);
+    }
+
+    #[test]
+    fn with_capacity_starts_empty() {
+        let mut sat = CnfSat::with_capacity(100, 1000);
+        assert_eq!((sat.variable_count(), sat.clause_count()), (0, 0));
+        assert_eq!(sat.to_dimacs(), CnfSat::new().to_dimacs());
+
+        let ids = sat.create_variables((0..200).map(|i| i.to_string()));
+        sat.ensure_at_least_one_set(&ids);
+        assert_eq!((sat.variable_count(), sat.clause_count()), (200, 1));
+    }
+
+    #[test]
+    fn duplicate_clauses_removed() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false), (v[0], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], false)]));
+        let group = sat.add_group();
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        sat.close_group(group);
+
+        // The copy in the group stays, so removing the group keeps the clause.
+        assert_eq!(sat.dedup_clauses(), 2);
+        assert_eq!(sat.to_dimacs(), "p cnf 2 3\n1 -2 0\n1 0\n1 0\n");
+        sat.remove_group(group);
+        assert_eq!(sat.to_dimacs(), "p cnf 2 2\n1 -2 0\n1 0\n");
+        assert_eq!(sat.dedup_clauses(), 0);
+    }
+
+    #[test]
+    fn units_propagate_along_implications() {
+        let (mut sat, v) = named_sat(&["a", "b", "c", "d", "e"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[2], false), (v[3], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false), (v[2], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], false), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[3], false), (v[4], true), (v[1], false)]));
+
+        let result = sat.propagate_units();
+        assert!(!result.conflict);
+        assert_eq!(
+            result.assignments,
+            vec![(v[0], true), (v[1], true), (v[2], true), (v[3], true), (v[4], true)]
+        );
+        assert_eq!(sat.to_dimacs(), "p cnf 5 5\n4 0\n3 0\n2 0\n1 0\n5 0\n");
+
+        sat.add_clause(CnfClause::from_literals(&[(v[4], false), (v[2], false)]));
+        assert!(sat.propagate_units().conflict);
+    }
+
+    #[test]
+    fn formula_statistics() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        assert_eq!(sat.stats().mean_clause_length, 0.0);
+        assert_eq!(sat.stats().max_clause_length, 0);
+
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], false), (v[1], true), (v[2], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false), (v[2], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[2], true)]));
+        sat.add_soft_clause(CnfClause::from_literals(&[(v[1], true)]), 1);
+
+        assert_eq!(
+            sat.stats(),
+            CnfStats {
+                variable_count: 3,
+                clause_count: 4,
+                literal_count: 7,
+                unit_clause_count: 2,
+                mean_clause_length: 1.75,
+                median_clause_length: 1.5,
+                max_clause_length: 3,
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn model_names_uncreated_variables_by_id() {
+        let (mut sat, v) = named_sat(&["a"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (VarId(3), false)]));
+        assert!(sat.to_dimacs().starts_with("p cnf 4 1\n"));
+
+        let result = sat.try_evaluate(fake_solver("s SATISFIABLE\\nv 1 -2 -3 -4 0\\n")).unwrap();
+        let EvaluationResult::Sat { model, .. } = result else {
+            panic!("Expected a model");
+        };
+        assert_eq!(model.get_result_by_name("a"), Some(true));
+        assert_eq!(model.get_result_by_id(VarId(3)), Some(false));
+        assert_eq!(model.get_result_by_name("#3"), Some(false));
+    }
+
+    /// The number of set values.
+    fn set_count(values: &[bool]) -> usize {
+        values.iter().filter(|&&value| value).count()
+    }
+
+    #[test]
+    fn at_most_k_matches_counting() {
+        for n in 0..=5 {
+            for k in 0..=n + 1 {
+                let (mut sat, v) = named_sat(&["a", "b", "c", "d", "e"][..n]);
+                sat.ensure_at_most_k(&v, k);
+                assert_holds_exactly_when(&sat, &v, |values| set_count(values) <= k);
+            }
+        }
+    }
+
+    #[test]
+    fn at_least_and_exactly_k_match_counting() {
+        for n in 0..=5 {
+            for k in 0..=n + 1 {
+                let (mut sat, v) = named_sat(&["a", "b", "c", "d", "e"][..n]);
+                sat.ensure_at_least_k(&v, k);
+                assert_holds_exactly_when(&sat, &v, |values| set_count(values) >= k);
+
+                let (mut sat, v) = named_sat(&["a", "b", "c", "d", "e"][..n]);
+                sat.ensure_exactly_k(&v, k);
+                assert_holds_exactly_when(&sat, &v, |values| set_count(values) == k);
+            }
+        }
+    }
+
+    #[test]
+    fn exactly_two_of_five_has_ten_models() {
+        let (mut sat, v) = named_sat(&["a", "b", "c", "d", "e"]);
+        sat.ensure_exactly_k(&v, 2);
+        let models = (0u32..1 << v.len())
+            .filter(|assignment| {
+                let literals: Vec<_> = (0..v.len()).map(|i| (v[i], assignment & (1 << i) != 0)).collect();
+                satisfiable_with(&sat, &literals)
+            })
+            .count();
+        assert_eq!(models, 10);
+    }
+
+    #[test]
+    fn xor_matches_parity() {
+        // Past four variables the constraint is split into a chain with auxiliary variables.
+        for n in 0..=7 {
+            for value in [false, true] {
+                let (mut sat, v) = named_sat(&["a", "b", "c", "d", "e", "f", "g"][..n]);
+                sat.ensure_xor(&v, value);
+                assert_holds_exactly_when(&sat, &v, |values| (set_count(values) % 2 == 1) == value);
+            }
+        }
+    }
+
+    #[test]
+    fn ladder_matches_pairwise() {
+        let names = ["a", "b", "c", "d", "e", "f", "g"];
+        for n in 0..=names.len() {
+            let (mut pairwise, v) = named_sat(&names[..n]);
+            pairwise.ensure_max_one_set_with(&v, AtMostOneEncoding::Pairwise);
+            assert_holds_exactly_when(&pairwise, &v, |values| set_count(values) <= 1);
+
+            for window in 1..=n + 1 {
+                let (mut ladder, v) = named_sat(&names[..n]);
+                ladder.ensure_max_one_set_with(&v, AtMostOneEncoding::Ladder { window });
+                assert_holds_exactly_when(&ladder, &v, |values| set_count(values) <= 1);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn solver_exiting_before_reading_reports_its_error() {
+        // Much more input than fits into the pipe, so writing it fails once the solver is gone.
+        let mut sat = CnfSat::new();
+        let v: Vec<_> = (0..50_000).map(|i| sat.create_variable(&format!("x{i}"))).collect();
+        for pair in v.windows(2) {
+            sat.add_clause(CnfClause::from_literals(&[(pair[0], true), (pair[1], false)]));
+        }
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("echo 'c unsupported input' >&2; exit 1");
+
+        match sat.try_evaluate(command) {
+            Err(SolverError::NonZeroExit { code, stderr }) => {
+                assert_eq!(code, Some(1));
+                assert_eq!(stderr, "c unsupported input\n");
+            }
+            _ => panic!("Expected the solver's exit to be reported"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn slow_solver_is_killed_on_timeout() {
+        let (mut sat, v) = named_sat(&["a"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("cat > /dev/null; echo 'c searching'; exec sleep 10");
+
+        let timeout = Duration::from_millis(300);
+        let result = sat.evaluate_with_timeout(command, timeout);
+        let EvaluationResult::Timeout { dimacs, solve_time, .. } = result else {
+            panic!("Expected a timeout");
+        };
+        assert_eq!(dimacs, "c searching\n");
+        assert!(solve_time >= timeout && solve_time < Duration::from_secs(5), "{solve_time:?}");
+
+        let result = sat.evaluate_with_timeout(fake_solver("s SATISFIABLE\\nv 1 0\\n"), Duration::from_secs(10));
+        assert_eq!(result.status(), "SAT");
+    }
+
+    #[test]
+    fn missing_solver_fails_to_spawn() {
+        let (sat, _) = named_sat(&["a"]);
+        let path = tempfile::tempdir().unwrap().path().join("missing-solver");
+        match sat.try_evaluate(Command::new(&path)) {
+            Err(SolverError::SpawnFailed { path: failed, source }) => {
+                assert_eq!(failed, path);
+                assert_eq!(source.kind(), io::ErrorKind::NotFound);
+            }
+            _ => panic!("Expected the spawn to fail"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn solver_without_execute_permission() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (sat, _) = named_sat(&["a"]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kissat");
+        fs::write(&path, "#!/bin/sh\necho 's SATISFIABLE'\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        match sat.try_evaluate(Command::new(&path)) {
+            Err(SolverError::NotExecutable { path: failed }) => assert_eq!(failed, path),
+            _ => panic!("Expected the solver to be reported as not executable"),
+        }
+    }
+
+    #[test]
+    fn permutation_grid_models_are_permutations() {
+        for n in 0..=4 {
+            let mut sat = CnfSat::new();
+            let grid: Vec<Vec<_>> = (0..n)
+                .map(|y| (0..n).map(|x| sat.create_variable(&format!("x{x}_y{y}"))).collect())
+                .collect();
+            sat.ensure_permutation(&grid);
+
+            let variables: Vec<_> = grid.concat();
+            let one_per_line = |values: &[bool]| {
+                (0..n).all(|y| set_count(&values[y * n..(y + 1) * n]) == 1)
+                    && (0..n).all(|x| (0..n).filter(|&y| values[y * n + x]).count() == 1)
+            };
+            assert_holds_exactly_when(&sat, &variables, one_per_line);
+        }
+    }
+
+    #[test]
+    fn restore_brings_back_removed_clauses() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        let group = sat.add_group();
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        sat.close_group(group);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true), (v[0], true)]));
+        sat.prefer(v[1], true);
+        let checkpoint = sat.checkpoint();
+        let before = sat.to_wcnf(WcnfVersion::default());
+
+        sat.remove_group(group);
+        assert_eq!(sat.dedup_clauses(), 1);
+        sat.prefer(v[1], false);
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true)]));
+        assert_eq!(sat.eliminate_subsumed(), 1);
+
+        sat.restore(checkpoint);
+        assert_eq!(sat.to_wcnf(WcnfVersion::default()), before);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn minimal_correction_set() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        for (id, value) in [(v[0], true), (v[0], false), (v[1], true), (v[1], false)] {
+            sat.add_clause(CnfClause::from_literals(&[(id, value)]));
+        }
+        // The first model, a and not b, satisfies clauses 0 and 3, the others cannot be added to them.
+        assert_eq!(sat.find_mcs(brute_force_solver).unwrap(), vec![1, 2]);
+
+        assert!(matches!(
+            sat.find_mcs(|| fake_solver("s UNKNOWN\\n")),
+            Err(SolverError::Undecided { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn all_three_models() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        let models = sat.all_models(brute_force_solver).unwrap();
+        let mut true_ids: Vec<_> = models.iter().map(SatModel::true_ids).collect();
+        true_ids.sort();
+        assert_eq!(true_ids, vec![vec![v[0]], vec![v[0], v[1]], vec![v[1]]]);
+
+        assert!(matches!(
+            sat.all_models(|| fake_solver("s UNKNOWN\\n")),
+            Err(SolverError::Undecided { .. })
+        ));
+    }
+
+    /// Every pigeon in exactly one hole, no two pigeons in the same hole.
+    fn pigeonhole(pigeons: usize, holes: usize) -> CnfSat {
+        let mut sat = CnfSat::new();
+        let grid: Vec<Vec<_>> = (0..pigeons)
+            .map(|p| (0..holes).map(|h| sat.create_variable(&format!("p{p}_h{h}"))).collect())
+            .collect();
+        for row in &grid {
+            sat.ensure_exactly_one_set(row);
+        }
+        for h in 0..holes {
+            let column: Vec<_> = grid.iter().map(|row| row[h]).collect();
+            sat.ensure_max_one_set(&column);
+        }
+        sat
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pigeonhole_model_counts() {
+        assert_eq!(pigeonhole(3, 3).count_models(brute_force_solver).unwrap(), 6);
+        assert_eq!(pigeonhole(2, 3).count_models(brute_force_solver).unwrap(), 6);
+        assert_eq!(pigeonhole(3, 2).count_models(brute_force_solver).unwrap(), 0);
+        // The hole of the first pigeon, wherever the second one is.
+        let sat = pigeonhole(2, 3);
+        let first_pigeon: Vec<_> = (0..3).map(|h| sat.get_variable(&format!("p0_h{h}"))).collect();
+        assert_eq!(sat.count_models_over(brute_force_solver, &first_pigeon).unwrap(), 3);
+    }
+
+    /// Hard clauses `a ∨ ¬b` and `b`, soft clauses `¬a` with weight 3 and `¬b ∨ c` with weight 5.
+    fn weighted_sat() -> CnfSat {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true)]));
+        sat.add_soft_clause(CnfClause::from_literals(&[(v[0], false)]), 3);
+        sat.add_soft_clause(CnfClause::from_literals(&[(v[1], false), (v[2], true)]), 5);
+        sat
+    }
+
+    #[test]
+    fn wcnf_output() {
+        let sat = weighted_sat();
+        assert_eq!(sat.to_wcnf(WcnfVersion::Legacy), "p wcnf 3 4 9\n9 1 -2 0\n9 2 0\n3 -1 0\n5 -2 3 0\n");
+        assert_eq!(sat.to_wcnf(WcnfVersion::Modern), "h 1 -2 0\nh 2 0\n3 -1 0\n5 -2 3 0\n");
+    }
+
+    /// Checks every line against the grammar of the MaxSAT evaluations since 2022:
+    /// `h` or a positive weight, then non-zero literals of declared variables and a final 0.
+    fn assert_modern_wcnf(wcnf: &str, variable_count: usize) {
+        for line in wcnf.lines() {
+            let mut tokens = line.split(' ');
+            let weight = tokens.next().unwrap();
+            assert!(weight == "h" || weight.parse::<u64>().is_ok_and(|weight| weight > 0), "{line}");
+            let literals: Vec<i64> = tokens.map(|token| token.parse().unwrap()).collect();
+            assert_eq!(literals.last(), Some(&0), "{line}");
+            let (_, literals) = literals.split_last().unwrap();
+            let declared = |literal: &i64| *literal != 0 && literal.unsigned_abs() as usize <= variable_count;
+            assert!(literals.iter().all(declared), "{line}");
+        }
+    }
+
+    #[test]
+    fn modern_wcnf_grammar() {
+        let mut sat = weighted_sat();
+        assert_modern_wcnf(&sat.to_wcnf(WcnfVersion::Modern), 3);
+        sat.prefer(VarId(2), false);
+        let wcnf = sat.to_wcnf_lex(WcnfVersion::Modern);
+        assert!(!wcnf.starts_with('p'));
+        assert_modern_wcnf(&wcnf, 3);
+        assert_eq!(wcnf.lines().filter(|line| line.starts_with("h ")).count(), 2);
+        assert_eq!(wcnf.lines().count(), 5);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn model_pinned_as_assumptions_is_found_again() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], false), (v[2], true)]));
+        // Every model, not only the first one the solver finds without assumptions.
+        for model in sat.all_models(brute_force_solver).unwrap() {
+            let assumptions = model.as_assumptions(&v);
+            assert_eq!(assumptions.len(), 3);
+            let EvaluationResult::Sat { model: resolved, .. } =
+                sat.evaluate_with_assumptions(brute_force_solver(), &assumptions)
+            else {
+                panic!("The model satisfies the formula");
+            };
+            assert_eq!(resolved.iter_ids().collect::<Vec<_>>(), model.iter_ids().collect::<Vec<_>>());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn solve_sequence_matches_separate_solves() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false), (v[2], true)]));
+        let assumption_sets = vec![
+            vec![],
+            vec![(v[0], false)],
+            vec![(v[0], false), (v[2], false)],
+            vec![(v[1], true), (v[2], true)],
+        ];
+
+        let describe = |result: &EvaluationResult| match result {
+            EvaluationResult::Sat { model, .. } => format!("SAT {:?}", model.iter_ids().collect::<Vec<_>>()),
+            result => result.status().to_string(),
+        };
+        let sequence: Vec<_> = sat
+            .solve_sequence(brute_force_solver, &assumption_sets)
+            .iter()
+            .map(describe)
+            .collect();
+        let separate: Vec<_> = assumption_sets
+            .iter()
+            .map(|assumptions| describe(&sat.evaluate_with_assumptions(brute_force_solver(), assumptions)))
+            .collect();
+        assert_eq!(sequence, separate);
+        assert_eq!(sequence[2], "UNSAT");
+    }
+
+    #[test]
+    fn trivially_unsat_formulas() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        assert_eq!(sat.trivially_unsat(), None);
+        assert!(!sat.is_trivially_unsat());
+
+        let mut contradictory = sat.clone();
+        contradictory.add_clause(CnfClause::from_literals(&[(v[1], true)]));
+        assert_eq!(contradictory.trivially_unsat(), Some(UnsatReason::ContradictoryUnits(v[1])));
+
+        let mut empty = sat.clone();
+        empty.add_clause(CnfClause::new());
+        empty.add_clause(CnfClause::from_literals(&[(v[0], false)]));
+        // The empty clause is reported before the contradictory units it comes with.
+        assert_eq!(empty.trivially_unsat(), Some(UnsatReason::EmptyClause(3)));
+
+        // Decided without running the solver, which would fail.
+        let result = empty.evaluate(Command::new("/nonexistent/solver"));
+        assert_eq!(result.status(), "UNSAT");
+        assert!(contradictory.is_trivially_unsat());
+    }
+
+    #[test]
+    fn remove_group_with_interleaved_clauses() {
+        let (mut sat, v) = named_sat(&["a", "b", "c", "d"]);
+        let unit = |id: VarId| CnfClause::from_literals(&[(id, true)]);
+        sat.add_clause(unit(v[0]));
+        let outer = sat.add_group();
+        sat.add_clause(unit(v[1]));
+        let inner = sat.add_group();
+        sat.add_clause(unit(v[2]));
+        sat.close_group(outer);
+        sat.add_clause(unit(v[3]));
+        sat.close_group(inner);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], false), (v[3], false)]));
+        let without_groups = "p cnf 4 2\n1 0\n-1 -4 0\n";
+
+        let mut removed_outer = sat.clone();
+        removed_outer.remove_group(outer);
+        assert_eq!(removed_outer.to_dimacs(), "p cnf 4 3\n1 0\n4 0\n-1 -4 0\n");
+        removed_outer.remove_group(inner);
+        assert_eq!(removed_outer.to_dimacs(), without_groups);
+
+        sat.remove_group(inner);
+        assert_eq!(sat.to_dimacs(), "p cnf 4 3\n1 0\n2 0\n-1 -4 0\n");
+        sat.remove_group(outer);
+        assert_eq!(sat.to_dimacs(), without_groups);
+    }
+
+    /// A solver that reads the formula, prints a comment and kills itself with the signal.
+    #[cfg(unix)]
+    fn signalled_solver(signal: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(format!("cat > /dev/null; echo 'c searching'; kill -{signal} $$"));
+        command
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn solver_killed_by_signal() {
+        let (mut sat, v) = named_sat(&["a"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+
+        for signal in ["TERM", "KILL", "XCPU"] {
+            match sat.try_evaluate(signalled_solver(signal)) {
+                Ok(EvaluationResult::Timeout { dimacs, .. }) => assert_eq!(dimacs, "c searching\n"),
+                _ => panic!("Expected SIG{signal} to be a timeout"),
+            }
+        }
+        // A crash is not a timeout.
+        match sat.try_evaluate(signalled_solver("SEGV")) {
+            Err(SolverError::NonZeroExit { code: None, .. }) => {}
+            _ => panic!("Expected SIGSEGV to be a failure"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn backbones_cannot_be_flipped() {
+        // a is a backbone, b and c are not.
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], true), (v[2], true)]));
+        let EvaluationResult::Sat { model, .. } = sat.evaluate(brute_force_solver()) else {
+            panic!("The formula is satisfiable");
+        };
+
+        assert!(sat.neighbor_model(brute_force_solver, &model, v[0]).unwrap().is_none());
+        for &id in &v[1..] {
+            let neighbor = sat.neighbor_model(brute_force_solver, &model, id).unwrap().unwrap();
+            assert_eq!(neighbor.get_result_by_id(id), model.get_result_by_id(id).map(|value| !value));
+            assert_eq!(neighbor.get_result_by_id(v[0]), Some(true));
+        }
+
+        let flippable = sat.flippable_variables(brute_force_solver, &model).unwrap();
+        assert_eq!(flippable, HashMap::from([(v[0], false), (v[1], true), (v[2], true)]));
+    }
+
+    #[test]
+    fn models_within_hamming_ball() {
+        let reference_values = [true, false, false, true];
+        let differing = |values: &[bool]| {
+            values.iter().zip(reference_values).filter(|&(&value, reference)| value != reference).count()
+        };
+        for distance in 0..=4 {
+            let (mut sat, v) = named_sat(&["a", "b", "c", "d"]);
+            let reference: Vec<_> = v.iter().copied().zip(reference_values).collect();
+            sat.ensure_within_hamming(&SatModel::from_vec(&sat, &reference), &v, distance);
+            assert_holds_exactly_when(&sat, &v, |values| differing(values) <= distance);
+        }
+    }
+
+    #[test]
+    fn variables_by_id_in_large_formula() {
+        let mut sat = CnfSat::new();
+        let ids = sat.create_variables((0..10_000).map(|i| format!("v{i}")));
+        for i in [0, 1, 4_999, 9_998, 9_999] {
+            let variable = sat.get_variable_by_id(ids[i]).unwrap();
+            assert_eq!(variable.name(), format!("v{i}"));
+            assert_eq!(variable.id(), VarId(i));
+            assert_eq!(sat.get_variable(&format!("v{i}")), ids[i]);
+        }
+        assert!(sat.get_variable_by_id(VarId(10_000)).is_none());
+    }
+
+    #[test]
+    fn exactly_one_of_three() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.ensure_exactly_one_set(&v);
+        assert_holds_exactly_when(&sat, &v, |values| set_count(values) == 1);
+        // One clause for at least one, three for the pairs.
+        assert_eq!(sat.clause_count(), 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn assumption_forces_value() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        let dimacs = sat.to_dimacs();
+
+        // Without assumptions, the first model sets a and leaves b unset.
+        let EvaluationResult::Sat { model, .. } = sat.evaluate(brute_force_solver()) else {
+            panic!("The formula is satisfiable");
+        };
+        assert_eq!(model.get_result_by_id(v[1]), Some(false));
+
+        let result = sat.evaluate_with_assumptions(brute_force_solver(), &[(v[0], false)]);
+        let EvaluationResult::Sat { model, .. } = result else {
+            panic!("The formula is satisfiable under the assumption");
+        };
+        assert_eq!(model.get_result_by_id(v[0]), Some(false));
+        assert_eq!(model.get_result_by_id(v[1]), Some(true));
+        assert_eq!(sat.to_dimacs(), dimacs);
+    }
+
+    #[test]
+    fn written_dimacs_matches_string() {
+        let (mut sat, v) = named_sat(&["a", "b", "c"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[2], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(VarId(6), true), (v[0], false)]));
+        sat.add_soft_clause(CnfClause::from_literals(&[(v[1], true)]), 2);
+        sat.add_clause(CnfClause::new());
+
+        let orders = [
+            ClauseLiteralOrder::InsertionOrder,
+            ClauseLiteralOrder::AscendingVar,
+            ClauseLiteralOrder::NegativeFirst,
+        ];
+        for order in orders {
+            sat.set_literal_order(order);
+            let mut written = Vec::new();
+            sat.write_dimacs(&mut written).unwrap();
+            assert_eq!(written, sat.to_dimacs().into_bytes());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn total_time_includes_solve_time() {
+        let (mut sat, v) = named_sat(&["a"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true)]));
+        let mut slow = Command::new("sh");
+        slow.arg("-c").arg("cat > /dev/null; sleep 0.1; printf 's SATISFIABLE\\nv 1 0\\n'");
+
+        let results = [
+            sat.evaluate(slow),
+            sat.evaluate_streaming(fake_solver("s SATISFIABLE\\nv 1 0\\n")),
+            sat.evaluate_with_timeout(fake_solver("s UNKNOWN\\n"), Duration::from_secs(10)),
+        ];
+        for result in &results {
+            assert!(result.solve_time() > Duration::ZERO, "{}", result.status());
+            assert!(result.total_time() >= result.solve_time(), "{}", result.status());
+        }
+        assert!(results[0].solve_time() >= Duration::from_millis(100));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unsat_core_of_assumptions() {
+        let (mut sat, v) = named_sat(&["a", "b", "c", "d"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], false), (v[1], false)]));
+
+        let assumptions = [(v[2], true), (v[0], true), (v[3], false), (v[1], true)];
+        let EvaluationResult::Unsat { core, .. } = sat.evaluate_with_assumptions(brute_force_solver(), &assumptions)
+        else {
+            panic!("a and b cannot both be set");
+        };
+        assert_eq!(core, assumptions);
+        let failed = sat.failed_assumptions(brute_force_solver, &assumptions);
+        assert_eq!(failed, Some(vec![(v[0], true), (v[1], true)]));
+
+        // Assumptions contradicting each other, whatever the clauses.
+        let contradictory = [(v[2], true), (v[3], true), (v[3], false)];
+        let failed = sat.failed_assumptions(brute_force_solver, &contradictory);
+        assert_eq!(failed, Some(vec![(v[3], true), (v[3], false)]));
+        assert_eq!(sat.failed_assumptions(brute_force_solver, &assumptions[..3]), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fake_solvers_write_proofs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[0], false)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false)]));
+
+        // Kissat gets the proof file as the last argument, glucose as an option.
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"#!/bin/sh
+eval "proof=\${$#}"
+for arg; do case "$arg" in -certified-output=*) proof="${arg#-certified-output=}";; esac; done
+cat > /dev/null
+echo "0" > "$proof"
+echo "s UNSATISFIABLE"
+exit 20
+"#;
+        for name in ["kissat", "glucose"] {
+            let path = dir.path().join(name);
+            fs::write(&path, script).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let options = SolveOptions {
+            solver_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        for solver in [Solver::Kissat, Solver::Glucose] {
+            let proof = dir.path().join(format!("{solver:?}.drat"));
+            assert_eq!(sat.evaluate_with_proof(&solver, &options, &proof).status(), "UNSAT");
+            assert_eq!(fs::read_to_string(&proof).unwrap(), "0\n", "{solver:?}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_based_solver_reads_and_writes_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false)]));
+
+        // Like minisat, the script takes the input and result files as its last two arguments
+        // and fails unless the formula is in the input file.
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"#!/bin/sh
+eval "input=\${$(($# - 1))}"
+eval "result=\${$#}"
+grep -q '^p cnf 2 2$' "$input" || exit 1
+printf 'SAT\n1 -2 0\n' > "$result"
+exit 10
+"#;
+        let path = dir.path().join(binary_name(&Solver::Minisat));
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let options = SolveOptions {
+            solver_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let EvaluationResult::Sat { model, .. } = sat.evaluate_with(&Solver::Minisat, &options) else {
+            panic!("the fake solver reports SAT");
+        };
+        assert_eq!(model.get_result_by_id(v[0]), Some(true));
+        assert_eq!(model.get_result_by_id(v[1]), Some(false));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn solver_name_comes_from_the_binary() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false)]));
+
+        let dir = tempfile::tempdir().unwrap();
+        let install = |name: &str, answer: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, format!("#!/bin/sh\ncat > /dev/null\nprintf '{answer}'\n")).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        let sat_solver = install("kissat", "s SATISFIABLE\\nv 1 -2 0\\n");
+        let unsat_solver = install("my-unsat-solver", "s UNSATISFIABLE\\n");
+
+        assert_eq!(sat.evaluate(Command::new(&sat_solver)).solver(), Some("kissat"));
+        assert_eq!(sat.evaluate(Command::new(&unsat_solver)).solver(), Some("my-unsat-solver"));
+
+        // A wrapper is the program that is run, the result still names the solver.
+        let options = SolveOptions {
+            solver_dir: Some(dir.path().to_path_buf()),
+            wrapper: vec!["env".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(sat.evaluate_with(&Solver::Kissat, &options).solver(), Some("kissat"));
+    }
+
+    #[test]
+    fn exactly_one_group_exactly_one() {
+        for sizes in [&[1, 1][..], &[2, 1], &[1, 2, 2], &[3]] {
+            let mut sat = CnfSat::new();
+            let groups: Vec<Vec<_>> = sizes
+                .iter()
+                .enumerate()
+                .map(|(g, &size)| (0..size).map(|i| sat.create_variable(&format!("g{g}_{i}"))).collect())
+                .collect();
+            let indicators = sat.ensure_exactly_one_group_exactly_one(&groups);
+            assert_eq!(indicators.len(), groups.len());
+
+            // The group variables in order, followed by the indicators.
+            let variables: Vec<_> = groups.iter().flatten().chain(&indicators).copied().collect();
+            assert_holds_exactly_when(&sat, &variables, |values| {
+                let (mut members, indicator_values) = values.split_at(variables.len() - indicators.len());
+                let counts: Vec<_> = sizes
+                    .iter()
+                    .map(|&size| {
+                        let (group, rest) = members.split_at(size);
+                        members = rest;
+                        set_count(group)
+                    })
+                    .collect();
+                counts.iter().filter(|&&count| count == 1).count() == 1
+                    && counts.iter().all(|&count| count <= 1)
+                    && counts.iter().zip(indicator_values).all(|(&count, &active)| active == (count == 1))
+            });
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn phase_hints_are_tried_first() {
+        let (mut sat, v) = named_sat(&["a", "b"]);
+        sat.add_clause(CnfClause::from_literals(&[(v[0], true), (v[1], true)]));
+
+        // Without hints, the brute force solver sets the lowest variables first.
+        let EvaluationResult::Sat { model, .. } = sat.evaluate_with_phases(brute_force_solver, &[]) else {
+            panic!("(a or b) is satisfiable");
+        };
+        assert_eq!(model.get_result_by_id(v[0]), Some(true));
+        assert_eq!(model.get_result_by_id(v[1]), Some(false));
+
+        let result = sat.evaluate_with_phases(brute_force_solver, &[(v[0], false)]);
+        let EvaluationResult::Sat { model, .. } = result else {
+            panic!("(a or b) is satisfiable with a unset");
+        };
+        assert_eq!(model.get_result_by_id(v[0]), Some(false));
+        assert_eq!(model.get_result_by_id(v[1]), Some(true));
+
+        // A hint no model agrees with is dropped.
+        sat.add_clause(CnfClause::from_literals(&[(v[1], false)]));
+        let result = sat.evaluate_with_phases(brute_force_solver, &[(v[0], false)]);
+        let EvaluationResult::Sat { model, .. } = result else {
+            panic!("(a or b) and not b is satisfiable");
+        };
+        assert_eq!(model.get_result_by_id(v[0]), Some(true));
+        assert_eq!(model.get_result_by_id(v[1]), Some(false));
+
+        sat.add_clause(CnfClause::from_literals(&[(v[0], false)]));
+        let result = sat.evaluate_with_phases(brute_force_solver, &[(v[0], true)]);
+        let EvaluationResult::Unsat { core, .. } = result else {
+            panic!("the formula is unsatisfiable");
+        };
+        assert!(core.is_empty());
     }
 }
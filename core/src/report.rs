@@ -0,0 +1,115 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::EvaluationResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Json,
+    Toml,
+}
+
+impl ReportFormat {
+    /// TOML for files with the `.toml` extension, JSON otherwise.
+    pub fn from_path(path: &Path) -> ReportFormat {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => ReportFormat::Toml,
+            _ => ReportFormat::Json,
+        }
+    }
+}
+
+/// Summary of a tool run, written with `--report`.
+#[derive(Serialize, Default)]
+pub struct RunReport {
+    pub solver: String,
+    pub solves: Vec<SolveRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backbones: Option<Vec<i64>>,
+}
+
+#[derive(Serialize)]
+pub struct SolveRecord {
+    pub label: String,
+    pub variables: usize,
+    pub clauses: usize,
+    pub result: String,
+    pub time_seconds: f64,
+    /// Including generating the input and parsing the output.
+    pub total_time_seconds: f64,
+}
+
+impl SolveRecord {
+    pub fn new(label: &str, variables: usize, clauses: usize, result: &EvaluationResult) -> Self {
+        SolveRecord {
+            label: label.to_string(),
+            variables,
+            clauses,
+            result: result.status().to_string(),
+            time_seconds: result.solve_time().as_secs_f64(),
+            total_time_seconds: result.total_time().as_secs_f64(),
+        }
+    }
+}
+
+impl RunReport {
+    /// Write the report in the given format, or the one matching the file extension.
+    pub fn write(&self, path: &Path, format: Option<ReportFormat>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        match format.unwrap_or_else(|| ReportFormat::from_path(path)) {
+            ReportFormat::Json => serde_json::to_writer_pretty(&mut writer, self)?,
+            ReportFormat::Toml => {
+                let toml = toml::to_string_pretty(self)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                writer.write_all(toml.as_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_report_round_trip() {
+        let report = RunReport {
+            solver: "Kissat".to_string(),
+            solves: vec![SolveRecord {
+                label: "8".to_string(),
+                variables: 512,
+                clauses: 9784,
+                result: "SAT".to_string(),
+                time_seconds: 0.25,
+                total_time_seconds: 0.5,
+            }],
+            backbones: Some(vec![1, -3]),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.toml");
+        report.write(&path, None).unwrap();
+
+        let written: toml::Table = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let expected: toml::Table = toml::from_str(
+            r#"
+            solver = "Kissat"
+            backbones = [1, -3]
+
+            [[solves]]
+            label = "8"
+            variables = 512
+            clauses = 9784
+            result = "SAT"
+            time_seconds = 0.25
+            total_time_seconds = 0.5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(written, expected);
+    }
+}
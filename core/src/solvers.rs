@@ -1,53 +1,746 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
-#[derive(Debug)]
+use crate::cli::CommonArgs;
+use crate::{CnfSat, EvaluationResult, SolverError};
+
+pub const DEFAULT_SOLVER_DIR: &str = "../solvers";
+/// Environment variable overriding [`DEFAULT_SOLVER_DIR`].
+pub const SOLVER_DIR_ENV: &str = "NAIL_SOLVER_DIR";
+
+/// Everything that configures a solver run besides the choice of the solver.
+/// Options the chosen solver has no equivalent for are ignored.
+#[derive(Debug, Default, Clone)]
+pub struct SolveOptions {
+    /// Directory with the solver binaries, [`default_solver_dir`] if not set.
+    /// A relative path is resolved against `working_dir` when that is set.
+    pub solver_dir: Option<PathBuf>,
+    /// Time limit enforced by the solver itself.
+    pub timeout: Option<Duration>,
+    /// Memory limit in megabytes, only supported by glucose and minisat.
+    pub memory_limit_mb: Option<u64>,
+    pub seed: Option<u64>,
+    /// Where to write the DRAT proof of unsatisfiability, not supported by oxisat, minisat and lingeling.
+    pub proof: Option<PathBuf>,
+    /// Program with arguments the solver is run through, e.g. `["taskset", "-c", "0"]`.
+    pub wrapper: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+    /// Arguments appended after all others.
+    pub extra_args: Vec<String>,
+    pub no_preprocessing: bool,
+    /// Generate the input while the solver reads it, see [`crate::CnfSat::evaluate_streaming`].
+    /// Not supported by file-based solvers.
+    pub stream: bool,
+    /// Input file of a file-based solver, `/dev/stdin` if not set.
+    pub input_file: Option<PathBuf>,
+    /// Result file of a file-based solver, `/dev/stdout` if not set.
+    pub result_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum Solver {
+    /// The first solver of [`AUTO_PRIORITY`] found in the solver directory, see [`detect_solver`].
+    #[default]
+    Auto,
     Kissat,
     Cadical,
     Oxisat,
     OxisatDpll,
     Glucose,
     GlucoseSyrup { threads: usize },
+    /// Reads the input from a file and writes the answer to a result file instead of stdout.
+    Minisat,
+    Lingeling,
+}
+
+/// The solvers [`Solver::Auto`] looks for, in order of preference.
+pub const AUTO_PRIORITY: [Solver; 6] = [
+    Solver::Kissat,
+    Solver::Cadical,
+    Solver::Glucose,
+    Solver::Lingeling,
+    Solver::Minisat,
+    Solver::Oxisat,
+];
+
+/// How a solver gets the formula and gives the answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolverInterface {
+    /// DIMACS on stdin, the answer on stdout.
+    Stdin,
+    /// The paths of the input file and of the result file as arguments.
+    Files,
+}
+
+impl Solver {
+    pub fn interface(&self) -> SolverInterface {
+        match self {
+            Solver::Minisat => SolverInterface::Files,
+            _ => SolverInterface::Stdin,
+        }
+    }
+}
+
+/// Anything that can decide a formula, e.g. an external solver process, an in-process solver or a mock.
+pub trait SatSolver {
+    fn solve(&self, sat: &CnfSat) -> EvaluationResult;
+}
+
+/// Runs an external solver for every formula, the way [`CnfSat::evaluate`] does.
+pub struct ProcessSolver {
+    command_factory: Box<dyn Fn() -> Command>,
+}
+
+impl ProcessSolver {
+    /// Like [`CnfSat::evaluate`], solving panics if the solver cannot be run,
+    /// e.g. when [`Solver::Auto`] finds no solver.
+    pub fn new(solver: Solver) -> ProcessSolver {
+        ProcessSolver::from_command_factory(move || {
+            build_command(&solver).unwrap_or_else(|err| panic!("{err}"))
+        })
+    }
+
+    pub fn with_options(solver: Solver, options: SolveOptions) -> ProcessSolver {
+        ProcessSolver::from_command_factory(move || {
+            build_command_with(&solver, &options).unwrap_or_else(|err| panic!("{err}"))
+        })
+    }
+
+    pub fn from_command_factory(command_factory: impl Fn() -> Command + 'static) -> ProcessSolver {
+        ProcessSolver {
+            command_factory: Box::new(command_factory),
+        }
+    }
+}
+
+impl SatSolver for ProcessSolver {
+    fn solve(&self, sat: &CnfSat) -> EvaluationResult {
+        sat.evaluate((self.command_factory)())
+    }
+}
+
+/// The solver chosen by the arguments, kissat for unknown names, see [`parse_solver_strict`].
+pub fn parse_solver(args: &CommonArgs) -> Solver {
+    parse_solver_strict(args).unwrap_or(Solver::Kissat)
+}
+
+/// The solver chosen by the first argument or by `--solver=<name>`, [`Solver::Auto`] if neither is given.
+/// Unknown names are an error. The installed solvers are not checked until one is run.
+pub fn parse_solver_strict(args: &CommonArgs) -> Result<Solver, String> {
+    let name = args.solver_option.as_deref().or(args.solver.as_deref());
+    match name {
+        None | Some("auto") => Ok(Solver::Auto),
+        Some("kissat") => Ok(Solver::Kissat),
+        Some("cadical") => Ok(Solver::Cadical),
+        Some("oxisat") => Ok(Solver::Oxisat),
+        Some("oxisat-dpll") => Ok(Solver::OxisatDpll),
+        Some("glucose") => Ok(Solver::Glucose),
+        Some("minisat") => Ok(Solver::Minisat),
+        Some("lingeling") => Ok(Solver::Lingeling),
+        Some("glucose-syrup") => Ok(Solver::GlucoseSyrup {
+            threads: syrup_threads(args.threads.as_deref()),
+        }),
+        Some(name) => Err(format!(
+            "Unknown solver {name:?}, expected one of auto, kissat, cadical, oxisat, oxisat-dpll, \
+             glucose, glucose-syrup, minisat or lingeling"
+        )),
+    }
+}
+
+/// The first solver of [`AUTO_PRIORITY`] whose binary exists in `solver_dir`.
+pub fn detect_solver(solver_dir: &Path) -> Result<Solver, SolverError> {
+    AUTO_PRIORITY
+        .into_iter()
+        .find(|solver| solver_path(solver, solver_dir).is_file())
+        .ok_or_else(|| SolverError::NoSolverFound {
+            solver_dir: solver_dir.to_path_buf(),
+        })
 }
 
-pub fn parse_solver(args: Vec<String>) -> Solver {
-    match args.get(1).map(|x| x.as_str()) {
-        None => Solver::Kissat,
-        Some("cadical") => Solver::Cadical,
-        Some("oxisat") => Solver::Oxisat,
-        Some("oxisat-dpll") => Solver::OxisatDpll,
-        Some("glucose") => Solver::Glucose,
-        Some("glucose-syrup") => {
-            let threads = args.get(2).and_then(|x| x.parse::<usize>().ok()).unwrap_or(1);
-            Solver::GlucoseSyrup { threads }
-        },
-        _ => Solver::Kissat
+impl Solver {
+    /// The solver itself, or for [`Solver::Auto`] the one detected in `solver_dir`.
+    pub fn resolve(&self, solver_dir: &Path) -> Result<Solver, SolverError> {
+        match self {
+            Solver::Auto => detect_solver(solver_dir),
+            solver => Ok(solver.clone()),
+        }
     }
 }
 
-pub fn build_command(solver: &Solver) -> Command {
+/// Evaluate all the formulas with up to `parallelism` solver processes running at the same time.
+/// The results are in the order of the formulas.
+///
+/// Like [`CnfSat::evaluate`], panics if the solver fails, but only once all the formulas are done.
+/// See [`try_evaluate_many`] for the errors and other commands.
+pub fn evaluate_many(formulas: Vec<CnfSat>, solver: &Solver, parallelism: usize) -> Vec<EvaluationResult> {
+    let solver = solver
+        .resolve(&default_solver_dir())
+        .unwrap_or_else(|err| panic!("{err}"));
+    let command_factory = || build_command(&solver).unwrap_or_else(|err| panic!("{err}"));
+    try_evaluate_many(formulas, command_factory, parallelism)
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|err| panic!("{err}")))
+        .collect()
+}
+
+/// Like [`evaluate_many`], with every solver started from a command made by `command_factory`.
+/// A solver failing on one formula does not affect the others.
+pub fn try_evaluate_many(
+    formulas: Vec<CnfSat>,
+    command_factory: impl Fn() -> Command + Sync,
+    parallelism: usize,
+) -> Vec<Result<EvaluationResult, SolverError>> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..formulas.len()).map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|scope| {
+        for _ in 0..parallelism.clamp(1, formulas.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(sat) = formulas.get(index) else {
+                    break;
+                };
+                let result = sat.try_evaluate(command_factory());
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("Every formula was evaluated"))
+        .collect()
+}
+
+/// Thread count for glucose-syrup, between 1 and the number of available CPUs.
+/// Invalid or out of range values are replaced with a warning on stderr.
+fn syrup_threads(threads: Option<&str>) -> usize {
+    let Some(threads) = threads else {
+        return 1;
+    };
+    let Ok(requested) = threads.parse::<usize>() else {
+        eprintln!("Warning: invalid thread count {threads:?}, using 1 thread");
+        return 1;
+    };
+
+    let cpus = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    if requested == 0 {
+        eprintln!("Warning: glucose-syrup needs at least 1 thread, using 1 thread");
+        1
+    } else if requested > cpus {
+        eprintln!("Warning: {requested} threads requested but only {cpus} CPUs available, using {cpus} threads");
+        cpus
+    } else {
+        requested
+    }
+}
+
+/// The directory in [`SOLVER_DIR_ENV`] if set, [`DEFAULT_SOLVER_DIR`] otherwise.
+pub fn default_solver_dir() -> PathBuf {
+    solver_dir_from(env::var_os(SOLVER_DIR_ENV))
+}
+
+fn solver_dir_from(dir: Option<OsString>) -> PathBuf {
+    dir.map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SOLVER_DIR))
+}
+
+pub fn build_command(solver: &Solver) -> Result<Command, SolverError> {
+    build_command_with(solver, &SolveOptions::default())
+}
+
+/// Name of the solver's binary. [`Solver::Auto`] has none of its own, the most preferred one is used.
+pub fn binary_name(solver: &Solver) -> &'static str {
+    match solver {
+        Solver::Auto | Solver::Kissat => "kissat",
+        Solver::Cadical => "cadical",
+        Solver::Oxisat | Solver::OxisatDpll => "oxisat",
+        Solver::Glucose => "glucose",
+        Solver::GlucoseSyrup { .. } => "glucose-syrup",
+        Solver::Minisat => "minisat",
+        Solver::Lingeling => "lingeling",
+    }
+}
+
+/// Path of the solver's binary: `NAIL_<NAME>_PATH` if set, e.g. `NAIL_GLUCOSE_SYRUP_PATH`,
+/// otherwise the binary in `solver_dir`.
+pub fn solver_path(solver: &Solver, solver_dir: &Path) -> PathBuf {
+    solver_path_from(solver, solver_dir, |variable| env::var_os(variable))
+}
+
+/// Like [`solver_path`], with the environment variables looked up by `env_var`.
+fn solver_path_from(solver: &Solver, solver_dir: &Path, env_var: impl Fn(&str) -> Option<OsString>) -> PathBuf {
+    let name = binary_name(solver);
+    let variable = format!("NAIL_{}_PATH", name.to_uppercase().replace('-', "_"));
+    env_var(&variable)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| solver_dir.join(name))
+}
+
+/// Build the command for a solver whose binaries live in `solver_dir`,
+/// without the files of file-based solvers, see [`add_files`].
+/// [`Solver::Auto`] is replaced with the solver detected in `solver_dir`, see [`Solver::resolve`],
+/// failing with [`SolverError::NoSolverFound`] if there is none.
+pub fn build_command_in(solver: &Solver, solver_dir: &Path) -> Result<Command, SolverError> {
+    let solver = &solver.resolve(solver_dir)?;
+    let mut command = Command::new(solver_path(solver, solver_dir));
     match solver {
-        Solver::Kissat => Command::new("../solvers/kissat"),
-        Solver::Cadical => Command::new("../solvers/cadical"),
+        Solver::Auto | Solver::Kissat | Solver::Cadical => {}
         Solver::Oxisat => {
-            let mut command = Command::new("../solvers/oxisat");
             command.arg("cdcl");
-            command
         }
         Solver::OxisatDpll => {
-            let mut command = Command::new("../solvers/oxisat");
             command.arg("dpll");
-            command
         }
         Solver::Glucose => {
-            let mut command = Command::new("../solvers/glucose");
             command.arg("-model");
-            command
         }
         Solver::GlucoseSyrup { threads } => {
-            let mut command = Command::new("../solvers/glucose-syrup");
             command.arg("-model").arg(format!("-nthreads={}", threads));
+        }
+        Solver::Minisat | Solver::Lingeling => {}
+    }
+    Ok(command)
+}
+
+/// Add the solver's own time limit option. Oxisat has no time limit, so it is left unchanged.
+/// Like the other `add_` functions, for [`Solver::Auto`] it uses the solver detected in
+/// [`default_solver_dir`] and adds nothing if there is none, which building the command reports.
+pub fn add_time_limit(solver: &Solver, command: &mut Command, limit: Duration) {
+    let seconds = limit.as_secs().max(1);
+    match solver {
+        Solver::Auto => {
+            if let Ok(solver) = solver.resolve(&default_solver_dir()) {
+                add_time_limit(&solver, command, limit);
+            }
+        }
+        Solver::Kissat => {
+            command.arg(format!("--time={}", seconds));
+        }
+        Solver::Cadical => {
+            command.arg("-t").arg(seconds.to_string());
+        }
+        Solver::Oxisat | Solver::OxisatDpll => {}
+        Solver::Glucose | Solver::GlucoseSyrup { .. } | Solver::Minisat => {
+            command.arg(format!("-cpu-lim={}", seconds));
+        }
+        Solver::Lingeling => {
+            command.arg("-T").arg(seconds.to_string());
+        }
+    }
+}
+
+/// Set the solver's random seed. Oxisat has no seed option, so it is left unchanged.
+pub fn add_seed(solver: &Solver, command: &mut Command, seed: u64) {
+    match solver {
+        Solver::Auto => {
+            if let Ok(solver) = solver.resolve(&default_solver_dir()) {
+                add_seed(&solver, command, seed);
+            }
+        }
+        Solver::Kissat | Solver::Cadical | Solver::Lingeling => {
+            command.arg(format!("--seed={}", seed));
+        }
+        Solver::Oxisat | Solver::OxisatDpll => {}
+        Solver::Glucose | Solver::GlucoseSyrup { .. } | Solver::Minisat => {
+            command.arg(format!("-rnd-seed={}", seed));
+        }
+    }
+}
+
+/// Disable preprocessing and inprocessing where the solver allows it.
+pub fn add_no_preprocessing(solver: &Solver, command: &mut Command) {
+    match solver {
+        Solver::Auto => {
+            if let Ok(solver) = solver.resolve(&default_solver_dir()) {
+                add_no_preprocessing(&solver, command);
+            }
+        }
+        Solver::Kissat | Solver::Cadical | Solver::Lingeling => {
+            command.arg("--plain");
+        }
+        Solver::Oxisat | Solver::OxisatDpll => {}
+        Solver::Glucose | Solver::GlucoseSyrup { .. } | Solver::Minisat => {
+            command.arg("-no-pre");
+        }
+    }
+}
+
+/// Write a DRAT proof to `path` when the formula is unsatisfiable. Minisat and lingeling
+/// do not write proofs, so they are left unchanged.
+/// Kissat and cadical take the proof file as a positional argument after the input,
+/// so the input is read from `/dev/stdin` explicitly.
+pub fn add_proof(solver: &Solver, command: &mut Command, path: &Path) {
+    match solver {
+        Solver::Auto => {
+            if let Ok(solver) = solver.resolve(&default_solver_dir()) {
+                add_proof(&solver, command, path);
+            }
+        }
+        Solver::Kissat | Solver::Cadical => {
+            command.arg("/dev/stdin").arg(path);
+        }
+        Solver::Oxisat | Solver::OxisatDpll | Solver::Minisat | Solver::Lingeling => {}
+        Solver::Glucose | Solver::GlucoseSyrup { .. } => {
             command
+                .arg("-certified")
+                .arg(format!("-certified-output={}", path.display()));
+        }
+    }
+}
+
+/// Pass the input and result files to a file-based solver, see [`SolverInterface::Files`].
+pub fn add_files(solver: &Solver, command: &mut Command, input: &Path, result: &Path) {
+    if solver.interface() == SolverInterface::Files {
+        command.arg(input).arg(result);
+    }
+}
+
+/// Build the solver command with all the options applied.
+/// File-based solvers read `/dev/stdin` and write `/dev/stdout` unless the options
+/// name other files, so the command can be run like any other.
+/// Fails with [`SolverError::NoSolverFound`] if [`Solver::Auto`] finds no solver.
+pub fn build_command_with(solver: &Solver, options: &SolveOptions) -> Result<Command, SolverError> {
+    let solver_dir = options.solver_dir.clone().unwrap_or_else(default_solver_dir);
+    let solver = &solver.resolve(&solver_dir)?;
+    let mut command = build_command_in(solver, &solver_dir)?;
+
+    if let Some(timeout) = options.timeout {
+        add_time_limit(solver, &mut command, timeout);
+    }
+    if let Some(memory_limit) = options.memory_limit_mb {
+        if let Solver::Glucose | Solver::GlucoseSyrup { .. } | Solver::Minisat = solver {
+            command.arg(format!("-mem-lim={}", memory_limit));
         }
     }
-}
\ No newline at end of file
+    if let Some(seed) = options.seed {
+        add_seed(solver, &mut command, seed);
+    }
+    if options.no_preprocessing {
+        add_no_preprocessing(solver, &mut command);
+    }
+    command.args(&options.extra_args);
+    // Positional arguments go last.
+    if let Some(proof) = &options.proof {
+        add_proof(solver, &mut command, proof);
+    }
+    let input = options.input_file.as_deref().unwrap_or(Path::new("/dev/stdin"));
+    let result = options.result_file.as_deref().unwrap_or(Path::new("/dev/stdout"));
+    add_files(solver, &mut command, input, result);
+
+    if let Some((program, arguments)) = options.wrapper.split_first() {
+        let mut wrapped = Command::new(program);
+        wrapped
+            .args(arguments)
+            .arg(command.get_program())
+            .args(command.get_args());
+        command = wrapped;
+    }
+    if let Some(working_dir) = &options.working_dir {
+        command.current_dir(working_dir);
+    }
+
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        common: CommonArgs,
+    }
+
+    fn parse_args(args: &[&str]) -> CommonArgs {
+        TestCli::parse_from(std::iter::once("test").chain(args.iter().copied())).common
+    }
+
+    #[test]
+    fn auto_is_not_detected_when_parsing() {
+        let args = parse_args(&["--solver-dir", "/nonexistent"]);
+        assert_eq!(parse_solver_strict(&args), Ok(Solver::Auto));
+    }
+
+    #[test]
+    fn auto_resolves_to_installed_solver() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(binary_name(&Solver::Glucose)), "").unwrap();
+        std::fs::write(dir.path().join(binary_name(&Solver::Minisat)), "").unwrap();
+
+        assert_eq!(Solver::Auto.resolve(dir.path()).unwrap(), Solver::Glucose);
+        assert_eq!(Solver::Cadical.resolve(dir.path()).unwrap(), Solver::Cadical);
+    }
+
+    #[test]
+    fn auto_without_solvers_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            Solver::Auto.resolve(dir.path()),
+            Err(SolverError::NoSolverFound { .. })
+        ));
+        assert!(matches!(
+            build_command_in(&Solver::Auto, dir.path()),
+            Err(SolverError::NoSolverFound { .. })
+        ));
+        let options = SolveOptions {
+            solver_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            build_command_with(&Solver::Auto, &options),
+            Err(SolverError::NoSolverFound { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn evaluate_many_keeps_order_and_failures() {
+        use crate::CnfClause;
+
+        let formulas: Vec<_> = (0..4)
+            .map(|index| {
+                let mut sat = CnfSat::new();
+                let id = sat.create_variable(&index.to_string());
+                sat.add_clause(CnfClause::from_literals(&[(id, index % 2 == 0)]));
+                sat
+            })
+            .collect();
+
+        // Fails on the formulas with a negative unit clause, finds a model for the others.
+        let results = try_evaluate_many(
+            formulas,
+            || {
+                let mut command = Command::new("sh");
+                command.arg("-c").arg(
+                    "read header; read clause; \
+                     case $clause in -*) exit 3;; *) printf 's SATISFIABLE\\nv 1 0\\n';; esac",
+                );
+                command
+            },
+            3,
+        );
+        let statuses: Vec<_> = results
+            .iter()
+            .map(|result| match result {
+                Ok(result) => result.status(),
+                Err(SolverError::NonZeroExit { code: Some(3), .. }) => "FAILED",
+                Err(err) => panic!("Unexpected error {err}"),
+            })
+            .collect();
+        assert_eq!(statuses, ["SAT", "FAILED", "SAT", "FAILED"]);
+    }
+
+    /// Tries every assignment, for formulas with a handful of variables.
+    struct BruteForce;
+
+    impl SatSolver for BruteForce {
+        fn solve(&self, sat: &CnfSat) -> EvaluationResult {
+            let ids: Vec<_> = sat.all_variables().collect();
+            let model = (0..1u64 << ids.len())
+                .map(|bits| {
+                    let literals = ids.iter().enumerate().map(|(bit, &id)| (id, bits >> bit & 1 == 1));
+                    crate::SatModel::from_vec(sat, &literals.collect())
+                })
+                .find(|model| sat.verify_model(model).is_ok());
+            let solver = "brute-force".to_string();
+            match model {
+                Some(model) => EvaluationResult::Sat {
+                    dimacs: String::new(),
+                    model,
+                    solver,
+                    solve_time: Duration::ZERO,
+                    total_time: Duration::ZERO,
+                },
+                None => EvaluationResult::Unsat {
+                    dimacs: String::new(),
+                    core: Vec::new(),
+                    solver,
+                    solve_time: Duration::ZERO,
+                    total_time: Duration::ZERO,
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn custom_solver_backend() {
+        use crate::CnfClause;
+
+        let mut sat = CnfSat::new();
+        let a = sat.create_variable("a");
+        let b = sat.create_variable("b");
+        sat.add_clause(CnfClause::from_literals(&[(a, true), (b, true)]));
+        sat.add_clause(CnfClause::from_literals(&[(a, false)]));
+
+        let EvaluationResult::Sat { model, solver, .. } = sat.evaluate_on(&BruteForce) else {
+            panic!("Expected a model");
+        };
+        assert_eq!(solver, "brute-force");
+        assert_eq!(model.true_ids(), vec![b]);
+
+        sat.add_clause(CnfClause::from_literals(&[(b, false)]));
+        assert_eq!(sat.evaluate_on(&BruteForce).status(), "UNSAT");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_solver_runs_command() {
+        use crate::CnfClause;
+
+        let mut sat = CnfSat::new();
+        let a = sat.create_variable("a");
+        sat.add_clause(CnfClause::from_literals(&[(a, false)]));
+
+        let solver = ProcessSolver::from_command_factory(|| {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg("cat > /dev/null; printf 's SATISFIABLE\\nv -1 0\\n'");
+            command
+        });
+        let EvaluationResult::Sat { model, .. } = sat.evaluate_on(&solver) else {
+            panic!("Expected a model");
+        };
+        assert_eq!(model.get_result_by_id(a), Some(false));
+    }
+
+    #[test]
+    fn solver_paths_from_environment() {
+        let custom = "/nonexistent/custom-lingeling";
+        let env_var = |variable: &str| match variable {
+            "NAIL_LINGELING_PATH" => Some(OsString::from(custom)),
+            "NAIL_GLUCOSE_SYRUP_PATH" => Some(OsString::from("/nonexistent/syrup")),
+            _ => None,
+        };
+        let solver_dir = Path::new("solvers");
+        assert_eq!(solver_path_from(&Solver::Lingeling, solver_dir, env_var), Path::new(custom));
+        assert_eq!(
+            solver_path_from(&Solver::GlucoseSyrup { threads: 2 }, solver_dir, env_var),
+            Path::new("/nonexistent/syrup")
+        );
+        assert_eq!(solver_path_from(&Solver::Kissat, solver_dir, env_var), Path::new("solvers/kissat"));
+
+        assert_eq!(solver_dir_from(Some(OsString::from("/opt/solvers"))), Path::new("/opt/solvers"));
+        assert_eq!(solver_dir_from(None), PathBuf::from(DEFAULT_SOLVER_DIR));
+    }
+
+    #[test]
+    fn syrup_thread_count_is_clamped() {
+        let cpus = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        assert_eq!(syrup_threads(None), 1);
+        assert_eq!(syrup_threads(Some("0")), 1);
+        assert_eq!(syrup_threads(Some("many")), 1);
+        assert_eq!(syrup_threads(Some("-2")), 1);
+        assert_eq!(syrup_threads(Some("1")), 1);
+        assert_eq!(syrup_threads(Some(&cpus.to_string())), cpus);
+        assert_eq!(syrup_threads(Some(&(cpus + 1).to_string())), cpus);
+        assert_eq!(syrup_threads(Some("100000")), cpus);
+    }
+
+    #[test]
+    fn solver_names_parsed_strictly() {
+        assert_eq!(parse_solver_strict(&parse_args(&[])), Ok(Solver::Auto));
+        assert_eq!(parse_solver_strict(&parse_args(&["cadical"])), Ok(Solver::Cadical));
+        assert_eq!(parse_solver_strict(&parse_args(&["--solver=glucose"])), Ok(Solver::Glucose));
+        assert_eq!(parse_solver_strict(&parse_args(&["--solver", "auto"])), Ok(Solver::Auto));
+        assert_eq!(
+            parse_solver_strict(&parse_args(&["glucose-syrup", "1"])),
+            Ok(Solver::GlucoseSyrup { threads: 1 })
+        );
+
+        let typo = parse_solver_strict(&parse_args(&["--solver=kisat"])).unwrap_err();
+        assert!(typo.starts_with("Unknown solver \"kisat\""), "{typo}");
+        assert!(parse_solver_strict(&parse_args(&["--solver="])).is_err());
+        assert_eq!(parse_solver(&parse_args(&["kisat"])), Solver::Kissat);
+    }
+
+    /// Answers formulas of unit clauses over distinct variables with the model of their literals.
+    #[cfg(unix)]
+    fn unit_solver() -> Command {
+        let mut command = Command::new("awk");
+        command.arg(
+            r#"BEGIN { printf "s SATISFIABLE\nv" } !/^p/ { for (i = 1; i < NF; i++) printf " %s", $i } END { print " 0" }"#,
+        );
+        command
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parallel_results_match_serial() {
+        use crate::{CnfClause, VarId};
+
+        let formulas: Vec<_> = (1..=6)
+            .map(|size| {
+                let mut sat = CnfSat::new();
+                for index in 0..size {
+                    let id = sat.create_variable(&format!("x{index}"));
+                    sat.add_clause(CnfClause::from_literals(&[(id, (index + size) % 3 == 0)]));
+                }
+                if size == 4 {
+                    // Contradictory units, decided without running the solver.
+                    sat.add_clause(CnfClause::from_literals(&[(VarId(0), true)]));
+                }
+                sat
+            })
+            .collect();
+
+        let describe = |result: &EvaluationResult| match result {
+            EvaluationResult::Sat { model, .. } => format!("SAT {:?}", model.iter_ids().collect::<Vec<_>>()),
+            result => result.status().to_string(),
+        };
+        let serial: Vec<_> = formulas
+            .iter()
+            .map(|sat| describe(&sat.try_evaluate(unit_solver()).unwrap()))
+            .collect();
+        let parallel: Vec<_> = try_evaluate_many(formulas, unit_solver, 4)
+            .iter()
+            .map(|result| describe(result.as_ref().unwrap()))
+            .collect();
+
+        assert_eq!(parallel, serial);
+        assert_eq!(serial[3], "UNSAT");
+        assert_eq!(serial[1], "SAT [(VarId(0), false), (VarId(1), true)]");
+    }
+
+    #[test]
+    fn minisat_and_lingeling_commands() {
+        let args = |solver: &Solver, options: &SolveOptions| -> Vec<String> {
+            let command = build_command_with(solver, options).unwrap();
+            command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let plain = SolveOptions {
+            solver_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let tuned = SolveOptions {
+            timeout: Some(Duration::from_secs(30)),
+            memory_limit_mb: Some(512),
+            seed: Some(7),
+            no_preprocessing: true,
+            input_file: Some(PathBuf::from("in.cnf")),
+            result_file: Some(PathBuf::from("out.txt")),
+            ..plain.clone()
+        };
+
+        assert_eq!(args(&Solver::Minisat, &plain), ["/dev/stdin", "/dev/stdout"]);
+        assert_eq!(
+            args(&Solver::Minisat, &tuned),
+            ["-cpu-lim=30", "-mem-lim=512", "-rnd-seed=7", "-no-pre", "in.cnf", "out.txt"]
+        );
+        // Lingeling reads stdin, so the files are not passed.
+        assert!(args(&Solver::Lingeling, &plain).is_empty());
+        assert_eq!(args(&Solver::Lingeling, &tuned), ["-T", "30", "--seed=7", "--plain"]);
+        assert_eq!(Solver::Minisat.interface(), SolverInterface::Files);
+        assert_eq!(Solver::Lingeling.interface(), SolverInterface::Stdin);
+    }
+}
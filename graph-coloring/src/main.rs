@@ -0,0 +1,198 @@
+use anyhow::anyhow;
+use clap::Parser;
+use itertools::iproduct;
+use std::collections::HashSet;
+use std::io::{stdin, Read};
+
+use core::cli::CommonArgs;
+use core::solvers::parse_solver_strict;
+use core::{CnfClause, CnfSat, EvaluationResult, SatModel};
+
+/// Decides whether a graph can be colored with k colors so that no edge connects two nodes
+/// of the same color. The edges are read from stdin, one pair of node names per line.
+#[derive(Parser)]
+#[command(name = "graph-coloring")]
+struct Cli {
+    #[command(flatten)]
+    common: CommonArgs,
+
+    /// Number of colors.
+    #[arg(short = 'k', long)]
+    colors: usize,
+}
+
+struct Graph {
+    // In the order of first appearance.
+    nodes: Vec<String>,
+    edges: Vec<(String, String)>,
+}
+
+fn main() -> Result<(), anyhow::Error> {
+    let cli = Cli::parse();
+    let args = cli.common;
+    let solver = parse_solver_strict(&args).map_err(anyhow::Error::msg)?;
+
+    let mut input = String::new();
+    stdin().read_to_string(&mut input)?;
+    let graph = parse_edges(&input)?;
+
+    let solver = args.resolve_solver(&solver)?;
+    if !args.quiet {
+        eprintln!("Using solver {solver:?}");
+    }
+
+    let mut sat = CnfSat::new();
+    add_color_vars(&mut sat, &graph, cli.colors);
+    add_coloring_restrictions(&mut sat, &graph, cli.colors);
+
+    if !args.quiet {
+        eprintln!(
+            "Coloring {} nodes and {} edges with {} colors, {} vars, {} clauses",
+            graph.nodes.len(),
+            graph.edges.len(),
+            cli.colors,
+            sat.variable_count(),
+            sat.clause_count()
+        );
+    }
+
+    if args.describe {
+        eprint!("{}", sat.to_readable());
+    }
+
+    match sat.try_evaluate(args.build_command(&solver)?)? {
+        EvaluationResult::Sat { model, solve_time, .. } => {
+            if !args.quiet {
+                eprintln!("Finished in {solve_time:?}, SAT");
+            }
+            for (node, color) in coloring_from_model(&model, &graph, cli.colors) {
+                println!("{node} {color}");
+            }
+        }
+        EvaluationResult::Unsat { .. } => println!("UNSAT"),
+        EvaluationResult::Unknown { solve_time, .. } => {
+            return Err(anyhow!("Solver gave up after {solve_time:?}."));
+        }
+        EvaluationResult::Timeout { solve_time, .. } => {
+            return Err(anyhow!("Solver timed out after {solve_time:?}."));
+        }
+    }
+
+    Ok(())
+}
+
+/// One edge per line as two node names separated by whitespace.
+/// Empty lines and lines starting with `#` are skipped.
+fn parse_edges(input: &str) -> Result<Graph, anyhow::Error> {
+    let mut graph = Graph {
+        nodes: Vec::new(),
+        edges: Vec::new(),
+    };
+    let mut known_nodes = HashSet::new();
+
+    for (line_number, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let endpoints: Vec<_> = line.split_whitespace().collect();
+        let [from, to] = endpoints[..] else {
+            return Err(anyhow!("Line {} is not an edge of two nodes: {line}", line_number + 1));
+        };
+        for node in [from, to] {
+            if known_nodes.insert(node) {
+                graph.nodes.push(node.to_string());
+            }
+        }
+        graph.edges.push((from.to_string(), to.to_string()));
+    }
+
+    Ok(graph)
+}
+
+fn node_color(node: &str, color: usize) -> String {
+    format!("v{}_c{}", node, color)
+}
+
+fn add_color_vars(sat: &mut CnfSat, graph: &Graph, colors: usize) {
+    sat.create_variables(iproduct!(&graph.nodes, 0..colors).map(|(node, color)| node_color(node, color)));
+}
+
+fn add_coloring_restrictions(sat: &mut CnfSat, graph: &Graph, colors: usize) {
+    // Each node has exactly one color
+    for node in &graph.nodes {
+        let vars: Vec<_> = (0..colors).map(|color| sat.get_variable(&node_color(node, color))).collect();
+        sat.ensure_exactly_one_set(&vars);
+    }
+
+    // The endpoints of an edge do not share a color
+    for ((from, to), color) in iproduct!(&graph.edges, 0..colors) {
+        let from = sat.get_variable(&node_color(from, color));
+        let to = sat.get_variable(&node_color(to, color));
+        sat.add_clause(CnfClause::from_literals(&[(from, false), (to, false)]));
+    }
+}
+
+fn coloring_from_model<'a>(model: &SatModel, graph: &'a Graph, colors: usize) -> Vec<(&'a str, usize)> {
+    graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let color = (0..colors)
+                .find(|&color| model.get_or_default(&node_color(node, color), false))
+                .expect("Every node has a color");
+            (node.as_str(), color)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::Literal;
+
+    fn coloring_formula(graph: &Graph, colors: usize) -> CnfSat {
+        let mut sat = CnfSat::new();
+        add_color_vars(&mut sat, graph, colors);
+        add_coloring_restrictions(&mut sat, graph, colors);
+        sat
+    }
+
+    /// Every assignment of the formula's variables.
+    fn all_models(sat: &CnfSat) -> impl Iterator<Item = SatModel> + '_ {
+        let ids: Vec<_> = sat.all_variables().collect();
+        (0..1u64 << ids.len()).map(move |bits| {
+            let literals: Vec<Literal> = ids
+                .iter()
+                .enumerate()
+                .map(|(bit, &id)| (id, bits >> bit & 1 == 1))
+                .collect();
+            SatModel::from_vec(sat, &literals)
+        })
+    }
+
+    #[test]
+    fn nodes_in_order_of_appearance() {
+        let graph = parse_edges("# triangle\na b\n\nb c\nc a\nc  d\n").unwrap();
+        assert_eq!(graph.nodes, ["a", "b", "c", "d"]);
+        assert_eq!(graph.edges.len(), 4);
+        assert!(parse_edges("a b c\n").is_err());
+    }
+
+    #[test]
+    fn triangle_needs_three_colors() {
+        let graph = parse_edges("a b\nb c\nc a\n").unwrap();
+
+        let sat = coloring_formula(&graph, 3);
+        let model = all_models(&sat)
+            .find(|model| sat.verify_model(model).is_ok())
+            .expect("A triangle has a 3-coloring");
+        let coloring = coloring_from_model(&model, &graph, 3);
+        let colors: HashSet<_> = coloring.iter().map(|&(_, color)| color).collect();
+        assert_eq!(colors.len(), 3);
+
+        let sat = coloring_formula(&graph, 2);
+        assert!(all_models(&sat).all(|model| sat.verify_model(&model).is_err()));
+    }
+}
@@ -1,34 +1,200 @@
+use clap::Parser;
 use itertools::iproduct;
+use std::path::PathBuf;
+use std::process::Command;
 
-use core::solvers::{build_command, parse_solver};
-use core::{CnfSat, EvaluationResult, SatModel};
+use core::cli::CommonArgs;
+use core::report::{RunReport, SolveRecord};
+use core::solvers::parse_solver_strict;
+use core::{AtMostOneEncoding, CnfClause, CnfSat, EvaluationResult, OptimizationResult, SatModel, WcnfVersion};
+
+/// Solves the n-queens problem for increasing n.
+#[derive(Parser)]
+#[command(name = "n-queens")]
+struct Cli {
+    #[command(flatten)]
+    common: CommonArgs,
+
+    /// Encode the diagonal constraints with the ladder at-most-one encoding using windows of this size.
+    #[arg(long)]
+    ladder_window: Option<usize>,
+
+    /// Stream the formula to the solver while generating it instead of building it in memory first.
+    #[arg(long)]
+    stream: bool,
+
+    /// Try the cells occupied on the previous board first, as phase hints for the solver.
+    /// The solve times include the attempt with the hints, compare them with a run without this flag.
+    #[arg(long, conflicts_with = "stream")]
+    warm_start: bool,
+
+    /// Only build the encodings and report unused variables, without solving.
+    #[arg(long)]
+    validate_only: bool,
+
+    /// Stop after the board of this size.
+    #[arg(long)]
+    max_n: Option<usize>,
+
+    /// Instead of solving, write the maximization of non-attacking queens on each board
+    /// as `peaceful-<n>.wcnf` for a MaxSAT solver into this directory. Needs `--max-n`.
+    #[arg(long, requires = "max_n")]
+    peaceful: Option<PathBuf>,
+
+    /// Run this MaxSAT solver on every `--peaceful` file, given as its only argument,
+    /// and report the maximum number of queens with a board.
+    #[arg(long, requires = "peaceful")]
+    maxsat_solver: Option<PathBuf>,
+}
 
 fn main() -> Result<(), anyhow::Error> {
-    let solver = parse_solver(std::env::args().collect());
+    let cli = Cli::parse();
+    let args = cli.common;
+    let diagonal_encoding = match cli.ladder_window {
+        None => AtMostOneEncoding::Pairwise,
+        Some(window) => AtMostOneEncoding::Ladder { window },
+    };
+    let mut solver = parse_solver_strict(&args).map_err(anyhow::Error::msg)?;
+
+    // Only an actual solve needs an installed solver
+    if !cli.validate_only && cli.peaceful.is_none() {
+        solver = args.resolve_solver(&solver)?;
+        if !args.quiet {
+            eprintln!("Using solver {solver:?}");
+        }
+    }
 
-    eprintln!("Using solver {solver:?}");
+    let mut report = RunReport {
+        solver: format!("{solver:?}"),
+        ..Default::default()
+    };
 
-    for n in 1.. {
-        let mut sat = CnfSat::new();
+    if args.csv {
+        println!("n,variables,clauses,result,time_seconds");
+    }
+
+    // The occupied cells of the last solved board, carried to the next one with --warm-start
+    let mut previous_cells = Vec::new();
+
+    for n in 1..=cli.max_n.unwrap_or(usize::MAX) {
+        if let Some(dir) = &cli.peaceful {
+            let mut sat = CnfSat::new();
+            add_peaceful_queens(&mut sat, n, diagonal_encoding);
+            let path = dir.join(format!("peaceful-{n}.wcnf"));
+            std::fs::write(&path, sat.to_wcnf(WcnfVersion::default()))?;
+            println!(
+                "Board {n}: {} vars, {} hard clauses, {} soft clauses, written to {}",
+                sat.variable_count(),
+                sat.clause_count(),
+                n * n,
+                path.display()
+            );
+
+            if let Some(maxsat_solver) = &cli.maxsat_solver {
+                let output = Command::new(maxsat_solver).arg(&path).output()?;
+                match sat.optimization_result_from_dimacs(&String::from_utf8_lossy(&output.stdout)) {
+                    OptimizationResult::Optimum { model, .. } => {
+                        let board = peaceful_map_from_model(&model, n);
+                        println!("Board {n}: at most {} peaceful queens:", board.matches('Q').count());
+                        println!("{board}");
+                    }
+                    OptimizationResult::Unsat => println!("Board {n}: UNSAT"),
+                    OptimizationResult::Unknown => println!("Board {n}: UNKNOWN"),
+                }
+            }
+            continue;
+        }
+
+        // A variable for every queen on every cell, and whether the cell is occupied
+        let mut sat = CnfSat::with_capacity(n * n * (n + 1), clause_estimate(n));
         add_queen_vars(&mut sat, n);
-        add_queen_restrictions(&mut sat, n);
-
-        println!(
-            "Starting {n}, {} vars, {} clauses",
-            sat.variable_count(),
-            sat.clause_count()
-        );
-
-        let result = sat.evaluate(build_command(&solver));
-        match result {
-            EvaluationResult::Sat { model, time, .. } => {
-                println!("Finished {n} in {time:?}, model:");
-                println!("{}", queen_map_from_model(&model, n));
+        add_queen_restrictions(&mut sat, n, diagonal_encoding);
+
+        if cli.validate_only {
+            let unused = sat.unused_variables();
+            println!(
+                "Board {n}: {} vars, {} clauses, {} unused variables",
+                sat.variable_count(),
+                sat.clause_count(),
+                unused.len()
+            );
+            for id in unused {
+                println!("  {}", sat.get_variable_by_id(id).unwrap().name());
             }
-            EvaluationResult::Unsat { time, .. } => {
-                println!("Finished {n} in {time:?}, UNSAT");
+            continue;
+        }
+
+        if !args.csv {
+            println!("Starting {n}, {}", sat.stats());
+        }
+
+        if args.describe {
+            eprint!("{}", sat.to_readable());
+        }
+
+        if let Some(runs) = args.runs {
+            let timing = sat.benchmark(
+                |seed| args.build_command_with_seed(&solver, seed).unwrap_or_else(|err| panic!("{err}")),
+                runs,
+            );
+            println!("Finished {n}, {timing}");
+            continue;
+        }
+
+        let result = if cli.stream {
+            sat.try_evaluate_streaming(args.build_command(&solver)?)?
+        } else if cli.warm_start {
+            let phases: Vec<_> = previous_cells
+                .iter()
+                .map(|&(x, y)| (sat.get_variable(&cell_pos(x, y)), true))
+                .collect();
+            sat.try_evaluate_with_phases(
+                || args.build_command(&solver).unwrap_or_else(|err| panic!("{err}")),
+                &phases,
+            )?
+        } else {
+            sat.try_evaluate(args.build_command(&solver)?)?
+        };
+        if args.csv {
+            println!(
+                "{n},{},{},{},{}",
+                sat.variable_count(),
+                sat.clause_count(),
+                result.status(),
+                result.solve_time().as_secs_f64()
+            );
+        } else {
+            match &result {
+                EvaluationResult::Sat { model, solve_time, .. } => {
+                    println!("Finished {n} in {solve_time:?}, model:");
+                    println!("{}", queen_map_from_model(model, n));
+                }
+                EvaluationResult::Unsat { solve_time, .. } => {
+                    println!("Finished {n} in {solve_time:?}, UNSAT");
+                }
+                EvaluationResult::Unknown { solve_time, .. } => {
+                    println!("Finished {n} in {solve_time:?}, UNKNOWN");
+                }
+                EvaluationResult::Timeout { solve_time, .. } => {
+                    println!("Finished {n} in {solve_time:?}, TIMEOUT");
+                }
             }
         }
+
+        if let EvaluationResult::Sat { model, .. } = &result {
+            previous_cells = occupied_cells(model, n);
+        }
+
+        if let Some(path) = &args.report {
+            // The search never ends on its own, so the report is rewritten after every board.
+            report.solves.push(SolveRecord::new(
+                &n.to_string(),
+                sat.variable_count(),
+                sat.clause_count(),
+                &result,
+            ));
+            report.write(path, args.format)?;
+        }
     }
 
     Ok(())
@@ -39,40 +205,38 @@ fn queen_pos(queen: usize, x: usize, y: usize) -> String {
 }
 
 fn add_queen_vars(sat: &mut CnfSat, n: usize) {
-    for queen in 0..n {
-        for y in 0..n {
-            for x in 0..n {
-                sat.create_variable(&queen_pos(queen, x, y));
-            }
-        }
-    }
+    sat.create_variables(iproduct!(0..n, 0..n, 0..n).map(|(queen, y, x)| queen_pos(queen, x, y)));
+    sat.create_variables(iproduct!(0..n, 0..n).map(|(y, x)| cell_pos(x, y)));
 }
 
-fn add_queen_restrictions(sat: &mut CnfSat, n: usize) {
+fn add_queen_restrictions(sat: &mut CnfSat, n: usize, diagonal_encoding: AtMostOneEncoding) {
     // Each queen is on exactly one position
     for queen in 0..n {
         let vars: Vec<_> = iproduct!(0..n, 0..n)
             .map(|(x, y)| sat.get_variable(&queen_pos(queen, x, y)))
             .collect();
-        sat.ensure_at_least_one_set(&vars);
-        sat.ensure_max_one_set(&vars);
+        sat.ensure_exactly_one_set(&vars);
     }
 
-    // No row has two queens
-    for y in 0..n {
-        let vars: Vec<_> = iproduct!(0..n, 0..n)
-            .map(|(x, queen)| sat.get_variable(&queen_pos(queen, x, y)))
-            .collect();
-        sat.ensure_max_one_set(&vars);
+    // A cell is occupied exactly when some queen is on it
+    for (y, x) in iproduct!(0..n, 0..n) {
+        let cell = sat.get_variable(&cell_pos(x, y));
+        let mut some_queen = CnfClause::new();
+        some_queen.set(cell, false);
+        for queen in 0..n {
+            let queen_var = sat.get_variable(&queen_pos(queen, x, y));
+            sat.ensure_implies(queen_var, cell);
+            some_queen.set(queen_var, true);
+        }
+        sat.add_clause(some_queen);
     }
 
-    // No column has two queens
-    for x in 0..n {
-        let vars: Vec<_> = iproduct!(0..n, 0..n)
-            .map(|(y, queen)| sat.get_variable(&queen_pos(queen, x, y)))
-            .collect();
-        sat.ensure_max_one_set(&vars);
-    }
+    // Every row and every column has exactly one occupied cell. Two queens on the same cell
+    // are on the same diagonals, so they are forbidden below.
+    let cells: Vec<Vec<_>> = (0..n)
+        .map(|y| (0..n).map(|x| sat.get_variable(&cell_pos(x, y))).collect())
+        .collect();
+    sat.ensure_permutation(&cells);
 
     // \ Diagonals starting at x=0
     for y_start in 0..n {
@@ -83,7 +247,7 @@ fn add_queen_restrictions(sat: &mut CnfSat, n: usize) {
             .map(|(queen, (x, y))| sat.get_variable(&queen_pos(queen, x, y)))
             .collect();
 
-        sat.ensure_max_one_set(&vars);
+        sat.ensure_max_one_set_with(&vars, diagonal_encoding);
     }
 
     // \ Diagonals starting at y=0, skipping x=0 (included in previous loop)
@@ -94,7 +258,7 @@ fn add_queen_restrictions(sat: &mut CnfSat, n: usize) {
         let vars: Vec<_> = iproduct!(0..n, x_range.zip(y_range))
             .map(|(queen, (x, y))| sat.get_variable(&queen_pos(queen, x, y)))
             .collect();
-        sat.ensure_max_one_set(&vars);
+        sat.ensure_max_one_set_with(&vars, diagonal_encoding);
     }
 
     // / Diagonals starting at x=0
@@ -105,7 +269,7 @@ fn add_queen_restrictions(sat: &mut CnfSat, n: usize) {
         let vars: Vec<_> = iproduct!(0..n, x_range.zip(y_range))
             .map(|(queen, (x, y))| sat.get_variable(&queen_pos(queen, x, y)))
             .collect();
-        sat.ensure_max_one_set(&vars);
+        sat.ensure_max_one_set_with(&vars, diagonal_encoding);
     }
 
     // / Diagonals starting at y=n-1, skipping x=0
@@ -116,8 +280,81 @@ fn add_queen_restrictions(sat: &mut CnfSat, n: usize) {
         let vars: Vec<_> = iproduct!(0..n, x_range.zip(y_range))
             .map(|(queen, (x, y))| sat.get_variable(&queen_pos(queen, x, y)))
             .collect();
+        sat.ensure_max_one_set_with(&vars, diagonal_encoding);
+    }
+}
+
+/// Clauses of the restrictions with the pairwise encoding, an estimate for the ladder encoding.
+fn clause_estimate(n: usize) -> usize {
+    let pairs = |count: usize| count * count.saturating_sub(1) / 2;
+    // Exactly one cell per queen, then the occupied cells as a permutation
+    let queens = n * (pairs(n * n) + 1);
+    let cells = n * n * (n + 1);
+    let lines = 2 * n * (pairs(n) + 1);
+    // Both directions have two diagonals of each length shorter than n and one of length n
+    let diagonals: usize = (1..=n)
+        .map(|length| if length < n { 4 } else { 2 } * pairs(n * length))
+        .sum();
+    queens + cells + lines + diagonals
+}
+
+fn cell_pos(x: usize, y: usize) -> String {
+    format!("cell_x{}_y{}", x, y)
+}
+
+/// The cells with a queen in the model, as `(x, y)`.
+fn occupied_cells(model: &SatModel, n: usize) -> Vec<(usize, usize)> {
+    iproduct!(0..n, 0..n)
+        .filter(|&(x, y)| model.get_or_default(&cell_pos(x, y), false))
+        .collect()
+}
+
+/// Any number of queens, one variable per cell, none attacking another.
+/// Every placed queen satisfies a soft clause, so the optimum places as many as possible.
+fn add_peaceful_queens(sat: &mut CnfSat, n: usize, diagonal_encoding: AtMostOneEncoding) {
+    for (y, x) in iproduct!(0..n, 0..n) {
+        let cell = sat.create_variable(&cell_pos(x, y));
+        let mut clause = CnfClause::new();
+        clause.set(cell, true);
+        sat.add_soft_clause(clause, 1);
+    }
+
+    for y in 0..n {
+        let vars: Vec<_> = (0..n).map(|x| sat.get_variable(&cell_pos(x, y))).collect();
+        sat.ensure_max_one_set(&vars);
+    }
+
+    for x in 0..n {
+        let vars: Vec<_> = (0..n).map(|y| sat.get_variable(&cell_pos(x, y))).collect();
         sat.ensure_max_one_set(&vars);
     }
+
+    // Diagonals indexed by x - y + n - 1 for \ and x + y for /
+    for diagonal in 0..2 * n - 1 {
+        let vars: Vec<_> = iproduct!(0..n, 0..n)
+            .filter(|&(x, y)| x + n - 1 - y == diagonal)
+            .map(|(x, y)| sat.get_variable(&cell_pos(x, y)))
+            .collect();
+        sat.ensure_max_one_set_with(&vars, diagonal_encoding);
+
+        let vars: Vec<_> = iproduct!(0..n, 0..n)
+            .filter(|&(x, y)| x + y == diagonal)
+            .map(|(x, y)| sat.get_variable(&cell_pos(x, y)))
+            .collect();
+        sat.ensure_max_one_set_with(&vars, diagonal_encoding);
+    }
+}
+
+fn peaceful_map_from_model(model: &SatModel, n: usize) -> String {
+    let mut output = String::new();
+    for y in 0..n {
+        for x in 0..n {
+            output.push(if model.get_or_default(&cell_pos(x, y), false) { 'Q' } else { '.' });
+        }
+        output.push('\n');
+    }
+
+    output
 }
 
 fn queen_map_from_model(model: &SatModel, n: usize) -> String {
@@ -127,9 +364,7 @@ fn queen_map_from_model(model: &SatModel, n: usize) -> String {
             let mut queen_placed = false;
 
             for queen in 0..n {
-                let result = model.get_result_by_name(&queen_pos(queen, x, y)).unwrap();
-
-                if result {
+                if model.get_or_default(&queen_pos(queen, x, y), false) {
                     output.push('Q');
                     queen_placed = true;
                 }
@@ -144,3 +379,40 @@ fn queen_map_from_model(model: &SatModel, n: usize) -> String {
 
     output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clause_estimate_is_exact_for_pairwise() {
+        for n in 1..=6 {
+            let mut sat = CnfSat::new();
+            add_queen_vars(&mut sat, n);
+            add_queen_restrictions(&mut sat, n, AtMostOneEncoding::Pairwise);
+            assert_eq!(clause_estimate(n), sat.clause_count(), "board {n}");
+        }
+    }
+
+    #[test]
+    fn peaceful_optimum_is_reported_as_a_board() {
+        let mut sat = CnfSat::new();
+        add_peaceful_queens(&mut sat, 3, AtMostOneEncoding::Pairwise);
+        // Two queens a knight's move apart, the maximum on a 3x3 board.
+        let top = sat.get_variable(&cell_pos(0, 0)).dimacs();
+        let bottom = sat.get_variable(&cell_pos(1, 2)).dimacs();
+        let output = format!("o 7\ns OPTIMUM FOUND\nv {top} {bottom} 0\n");
+        let OptimizationResult::Optimum { cost, model } = sat.optimization_result_from_dimacs(&output) else {
+            panic!("expected an optimum");
+        };
+        assert_eq!(cost, 7);
+        assert_eq!(peaceful_map_from_model(&model, 3), "Q..\n...\n.Q.\n");
+    }
+
+    #[test]
+    fn peaceful_mode_needs_a_bound() {
+        assert!(Cli::try_parse_from(["n-queens", "--peaceful", "out"]).is_err());
+        assert!(Cli::try_parse_from(["n-queens", "--peaceful", "out", "--max-n", "4"]).is_ok());
+        assert!(Cli::try_parse_from(["n-queens", "--maxsat-solver", "solver", "--max-n", "4"]).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::anyhow;
+use clap::Parser;
+use itertools::iproduct;
+use std::io::{stdin, Read};
+
+use core::cli::CommonArgs;
+use core::solvers::parse_solver_strict;
+use core::{CnfClause, CnfSat, EvaluationResult, SatModel};
+
+/// Solves a 9x9 sudoku read from stdin, one row per line with `.` for blank cells.
+#[derive(Parser)]
+#[command(name = "sudoku")]
+struct Cli {
+    #[command(flatten)]
+    common: CommonArgs,
+}
+
+type Grid = [[Option<usize>; 9]; 9];
+
+fn main() -> Result<(), anyhow::Error> {
+    let cli = Cli::parse();
+    let args = cli.common;
+    let solver = parse_solver_strict(&args).map_err(anyhow::Error::msg)?;
+
+    let mut input = String::new();
+    stdin().read_to_string(&mut input)?;
+    let grid = parse_grid(&input)?;
+
+    let solver = args.resolve_solver(&solver)?;
+    if !args.quiet {
+        eprintln!("Using solver {solver:?}");
+    }
+
+    let mut sat = CnfSat::new();
+    add_cell_vars(&mut sat);
+    add_sudoku_restrictions(&mut sat);
+    add_clues(&mut sat, &grid);
+
+    if !args.quiet {
+        eprintln!(
+            "Solving with {} vars, {} clauses",
+            sat.variable_count(),
+            sat.clause_count()
+        );
+    }
+
+    if args.describe {
+        eprint!("{}", sat.to_readable());
+    }
+
+    match sat.try_evaluate(args.build_command(&solver)?)? {
+        EvaluationResult::Sat { model, solve_time, .. } => {
+            if !args.quiet {
+                eprintln!("Finished in {solve_time:?}, SAT");
+            }
+            print!("{}", grid_from_model(&model));
+        }
+        EvaluationResult::Unsat { .. } => println!("UNSAT"),
+        EvaluationResult::Unknown { solve_time, .. } => {
+            return Err(anyhow!("Solver gave up after {solve_time:?}."));
+        }
+        EvaluationResult::Timeout { solve_time, .. } => {
+            return Err(anyhow!("Solver timed out after {solve_time:?}."));
+        }
+    }
+
+    Ok(())
+}
+
+/// Nine rows of nine cells, each a digit or `.`. Whitespace within rows is ignored.
+fn parse_grid(input: &str) -> Result<Grid, anyhow::Error> {
+    let rows: Vec<Vec<char>> = input
+        .lines()
+        .map(|line| line.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>())
+        .filter(|row| !row.is_empty())
+        .collect();
+    if rows.len() != 9 {
+        return Err(anyhow!("Expected 9 rows, found {}", rows.len()));
+    }
+
+    let mut grid = [[None; 9]; 9];
+    for (r, row) in rows.iter().enumerate() {
+        if row.len() != 9 {
+            return Err(anyhow!("Row {} has {} cells, expected 9", r + 1, row.len()));
+        }
+        for (c, &cell) in row.iter().enumerate() {
+            grid[r][c] = match cell {
+                '.' => None,
+                '1'..='9' => Some(cell as usize - '0' as usize),
+                _ => return Err(anyhow!("Invalid cell {cell:?} in row {}", r + 1)),
+            };
+        }
+    }
+
+    Ok(grid)
+}
+
+fn cell_value(r: usize, c: usize, v: usize) -> String {
+    format!("cell_r{}_c{}_v{}", r, c, v)
+}
+
+fn add_cell_vars(sat: &mut CnfSat) {
+    sat.create_variables(iproduct!(0..9, 0..9, 1..=9).map(|(r, c, v)| cell_value(r, c, v)));
+}
+
+fn add_sudoku_restrictions(sat: &mut CnfSat) {
+    // Each cell has exactly one value
+    for (r, c) in iproduct!(0..9, 0..9) {
+        let vars: Vec<_> = (1..=9).map(|v| sat.get_variable(&cell_value(r, c, v))).collect();
+        sat.ensure_exactly_one_set(&vars);
+    }
+
+    // Each row has each value exactly once
+    for (r, v) in iproduct!(0..9, 1..=9) {
+        let vars: Vec<_> = (0..9).map(|c| sat.get_variable(&cell_value(r, c, v))).collect();
+        sat.ensure_exactly_one_set(&vars);
+    }
+
+    // Each column has each value exactly once
+    for (c, v) in iproduct!(0..9, 1..=9) {
+        let vars: Vec<_> = (0..9).map(|r| sat.get_variable(&cell_value(r, c, v))).collect();
+        sat.ensure_exactly_one_set(&vars);
+    }
+
+    // Each 3x3 box has each value exactly once
+    for (box_r, box_c, v) in iproduct!(0..3, 0..3, 1..=9) {
+        let vars: Vec<_> = iproduct!(0..3, 0..3)
+            .map(|(r, c)| sat.get_variable(&cell_value(3 * box_r + r, 3 * box_c + c, v)))
+            .collect();
+        sat.ensure_exactly_one_set(&vars);
+    }
+}
+
+fn add_clues(sat: &mut CnfSat, grid: &Grid) {
+    for (r, c) in iproduct!(0..9, 0..9) {
+        if let Some(v) = grid[r][c] {
+            let mut clause = CnfClause::new();
+            clause.set(sat.get_variable(&cell_value(r, c, v)), true);
+            sat.add_clause(clause);
+        }
+    }
+}
+
+fn grid_from_model(model: &SatModel) -> String {
+    let mut output = String::new();
+    for r in 0..9 {
+        for c in 0..9 {
+            let value = (1..=9)
+                .find(|&v| model.get_or_default(&cell_value(r, c, v), false))
+                .expect("Every cell has a value");
+            output.push_str(&value.to_string());
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "\
+53..7....
+6..195...
+.98....6.
+8...6...3
+4..8.3..1
+7...2...6
+.6....28.
+...419..5
+....8..79
+";
+
+    const SOLUTION: &str = "\
+534678912
+672195348
+198342567
+859761423
+426853791
+713924856
+961537284
+287419635
+345286179
+";
+
+    fn sudoku_formula(grid: &Grid) -> CnfSat {
+        let mut sat = CnfSat::new();
+        add_cell_vars(&mut sat);
+        add_sudoku_restrictions(&mut sat);
+        add_clues(&mut sat, grid);
+        sat
+    }
+
+    fn model_of(sat: &CnfSat, solution: &Grid) -> SatModel {
+        let literals: Vec<_> = iproduct!(0..9, 0..9, 1..=9)
+            .map(|(r, c, v)| (sat.get_variable(&cell_value(r, c, v)), solution[r][c] == Some(v)))
+            .collect();
+        SatModel::from_vec(sat, &literals)
+    }
+
+    #[test]
+    fn parses_grid() {
+        let grid = parse_grid(PUZZLE).unwrap();
+        assert_eq!(grid[0][..3], [Some(5), Some(3), None]);
+        assert_eq!(grid[8][8], Some(9));
+        assert!(parse_grid("123\n").is_err());
+        assert!(parse_grid(&PUZZLE.replace('7', "x")).is_err());
+    }
+
+    #[test]
+    fn known_puzzle_has_its_unique_solution() {
+        let mut sat = sudoku_formula(&parse_grid(PUZZLE).unwrap());
+        let solution = parse_grid(SOLUTION).unwrap();
+
+        let model = model_of(&sat, &solution);
+        assert_eq!(sat.verify_model(&model), Ok(()));
+        assert_eq!(grid_from_model(&model), SOLUTION);
+
+        let mut wrong = solution;
+        wrong[0].swap(0, 1);
+        assert!(sat.verify_model(&model_of(&sat, &wrong)).is_err());
+
+        // The clues force every cell, so no other solution exists.
+        let propagation = sat.propagate_units();
+        assert!(!propagation.conflict);
+        let forced: Vec<_> = propagation.assignments.iter().filter(|&&(_, value)| value).collect();
+        assert_eq!(forced.len(), 81);
+        assert!(forced.iter().all(|&&(id, _)| model.get_result_by_id(id) == Some(true)));
+    }
+}